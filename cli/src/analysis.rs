@@ -0,0 +1,100 @@
+use crate::{replay_rules, replays, time_format};
+use core::{Analysis, Colour, GameMode, Ruleset};
+use std::time::Duration;
+
+// How many of the slowest pieces are highlighted
+const SLOWEST: usize = 5;
+
+fn seconds(ms: u128) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+// The rates on one line, also shown after every game
+pub fn summary(analysis: &Analysis) -> String {
+    format!(
+        "PPS: {:.2}  KPP: {:.2}  LPM: {:.1}  APM: {:.1}",
+        analysis.pps(),
+        analysis.kpp(),
+        analysis.lpm(),
+        analysis.apm()
+    )
+}
+
+fn print_table(analysis: &Analysis) {
+    let slowest = analysis.slowest(SLOWEST);
+
+    println!(" {}", summary(analysis));
+    println!(
+        " Pieces: {}  Keys: {}  Lines: {}  Attack: {}  {}",
+        analysis.pieces().len(),
+        analysis.keys(),
+        analysis.lines(),
+        analysis.attack(),
+        time_format(Duration::from_millis(analysis.duration() as u64))
+    );
+    println!("--------------------");
+    println!("     #  Piece    Start    Time  Keys  Lines  Attack");
+
+    for (i, piece) in analysis.pieces().iter().enumerate() {
+        let line = format!(
+            " {:>5}  {:>5}  {:>7}  {:>6}  {:>4}  {:>5}  {:>6}",
+            i + 1,
+            format!("{:?}", piece.piece),
+            seconds(piece.start),
+            seconds(piece.time()),
+            piece.keys,
+            piece.lines,
+            piece.attack
+        );
+
+        if slowest.contains(&i) {
+            println!("\x1b[38;5;{}m{}  slow\x1b[0m", Colour::Red as u8, line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+fn print_csv(analysis: &Analysis) {
+    let slowest = analysis.slowest(SLOWEST);
+
+    println!("piece,type,start_ms,end_ms,time_ms,keys,lines,attack,slowest");
+
+    for (i, piece) in analysis.pieces().iter().enumerate() {
+        println!(
+            "{},{:?},{},{},{},{},{},{},{}",
+            i + 1,
+            piece.piece,
+            piece.start,
+            piece.end,
+            piece.time(),
+            piece.keys,
+            piece.lines,
+            piece.attack,
+            slowest.contains(&i)
+        );
+    }
+}
+
+// Speed and efficiency of a replay with the time each piece took, as a table or as csv
+pub fn report(path: &str, format: &str, rules: Ruleset) {
+    let replay = match replays::load(path) {
+        Ok(replay) => replay,
+        Err(err) => return println!("{}", err),
+    };
+
+    if replay.kind().mode() == GameMode::Versus {
+        return println!(" Only single player replays can be analysed");
+    }
+
+    let rules = match replay_rules(&replay, rules) {
+        Some(rules) => rules,
+        None => return println!(" Replay was recorded with a different ruleset"),
+    };
+
+    match format {
+        "table" => print_table(&replay.analyse(&rules)),
+        "csv" => print_csv(&replay.analyse(&rules)),
+        _ => println!(" Format must be table or csv"),
+    }
+}
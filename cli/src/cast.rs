@@ -0,0 +1,87 @@
+use crate::{config::Config, display, game_screen, replay_rules, replays};
+use core::{json::quote, GameMode, Playback, Ruleset};
+use std::{io::Write, time::Duration};
+
+/*
+ * An asciicast v2 recording, a header line and then a line for each frame
+ * with its time in seconds and what was printed
+ *
+ * {"version": 2, "width": 80, "height": 25}
+ * [0.050, "o", "\u001b[0;0H..."]
+ */
+fn cast(conf: Config, mut playback: Playback, title: &str, time_stamp: i64) -> String {
+    let frame_time = conf.frame_time.max(1) as u128;
+
+    // A row more than the screen, which the line break after the last row would otherwise scroll
+    let mut cast = format!(
+        "{{\"version\": 2, \"width\": 80, \"height\": 25, \"timestamp\": {}, \"title\": {}}}\n",
+        time_stamp / 1000,
+        quote(title)
+    );
+
+    let mut event = |tick: u128, output: &str| {
+        // Terminals get a carriage return with every line break, so the cast needs them written out
+        let output = output.replace('\n', "\r\n");
+        cast.push_str(&format!(
+            "[{:.3}, \"o\", {}]\n",
+            tick as f64 / 1000.0,
+            quote(&output)
+        ));
+    };
+
+    event(0, display::CLEAR);
+
+    let mut last = String::new();
+    let mut tick = 0;
+
+    // Frames are drawn as often as when playing, those that change nothing are left out
+    loop {
+        let frame =
+            game_screen(conf, playback.game(), &Duration::from_millis(tick as u64)).render();
+
+        if frame != last {
+            event(tick, &frame);
+            last = frame;
+        }
+
+        if tick >= playback.duration() || playback.over() {
+            break;
+        }
+
+        tick = (tick + frame_time).min(playback.duration());
+        playback.seek(tick);
+    }
+
+    cast
+}
+
+// Plays a replay through without a terminal, writing what would have been shown as an asciinema recording
+pub fn render(conf: Config, path: &str, output: Option<&str>, rules: Ruleset) {
+    let replay = match replays::load(path) {
+        Ok(replay) => replay,
+        Err(err) => return println!("{}", err),
+    };
+
+    if replay.kind().mode() == GameMode::Versus {
+        return println!(" Only single player replays can be rendered");
+    }
+
+    let rules = match replay_rules(&replay, rules) {
+        Some(rules) => rules,
+        None => return println!(" Replay was recorded with a different ruleset"),
+    };
+
+    let title = format!("{} - {}", replay.username(), replay.kind());
+    let time_stamp = replay.time_stamp();
+    let cast = cast(conf, Playback::new(replay, &rules), &title, time_stamp);
+
+    match output {
+        Some(output) => match std::fs::write(output, cast) {
+            Ok(_) => println!(" Recording saved to: {}", output),
+            Err(err) => println!(" {}: {}", output, err),
+        },
+        None => {
+            let _ = std::io::stdout().write_all(cast.as_bytes());
+        }
+    }
+}
@@ -1,8 +1,61 @@
+use core::{GameType, Ruleset};
 use std::path::Path;
 
+// Keys for one player of a versus match
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyMap {
+    pub hold: char,
+    pub left: char,
+    pub right: char,
+    pub soft_drop: char,
+    pub hard_drop: char,
+    pub rotate_90: char,
+    pub rotate_180: char,
+    pub rotate_270: char,
+}
+
+impl KeyMap {
+    const PLAYER_ONE: Self = Self {
+        hold: 'f',
+        left: 'a',
+        right: 'd',
+        soft_drop: 's',
+        hard_drop: 'w',
+        rotate_90: 'r',
+        rotate_180: '\0',
+        rotate_270: 'e',
+    };
+
+    const PLAYER_TWO: Self = Self {
+        hold: 'h',
+        left: 'j',
+        right: 'l',
+        soft_drop: 'k',
+        hard_drop: 'i',
+        rotate_90: 'o',
+        rotate_180: '\0',
+        rotate_270: 'u',
+    };
+
+    fn set(&mut self, option: &str, key: char) {
+        match option {
+            "hold" => self.hold = key,
+            "left" => self.left = key,
+            "right" => self.right = key,
+            "soft_drop" => self.soft_drop = key,
+            "hard_drop" => self.hard_drop = key,
+            "rotate_90" => self.rotate_90 = key,
+            "rotate_180" => self.rotate_180 = key,
+            "rotate_270" => self.rotate_270 = key,
+            _ => {}
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Config {
     pub quit: char,
+    pub pause: char,
     pub hold: char,
     pub left: char,
     pub right: char,
@@ -11,14 +64,26 @@ pub struct Config {
     pub rotate_90: char,
     pub rotate_180: char,
     pub rotate_270: char,
+    pub swap_hold: char,
+    pub toggle: char,
+    pub garbage: char,
+    pub cursor_up: char,
+    pub cursor_down: char,
+    pub cursor_left: char,
+    pub cursor_right: char,
+    pub players: [KeyMap; 2],
+    pub garbage_delay: u16,
     pub fps: u16,
     pub frame_time: u16,
+    pub stats: bool,
+    pub mode: GameType,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             quit: 'q',
+            pause: 'p',
             hold: 'c',
             left: ',',
             right: '-',
@@ -27,8 +92,19 @@ impl Default for Config {
             rotate_90: 'x',
             rotate_180: '\0',
             rotate_270: 'z',
+            swap_hold: 'v',
+            toggle: 'o',
+            garbage: 'g',
+            cursor_up: 'i',
+            cursor_down: 'k',
+            cursor_left: 'j',
+            cursor_right: 'l',
+            players: [KeyMap::PLAYER_ONE, KeyMap::PLAYER_TWO],
+            garbage_delay: 500,
             fps: 20,
             frame_time: 50,
+            stats: false,
+            mode: GameType::new_lines(40),
         }
     }
 }
@@ -52,6 +128,7 @@ impl From<&str> for Config {
                 if let Some(value) = split.next() {
                     if let Some(key) = key(value) {
                         match option.trim() {
+                            "pause" => config.pause = key,
                             "hold" => config.hold = key,
                             "left" => config.left = key,
                             "right" => config.right = key,
@@ -60,12 +137,36 @@ impl From<&str> for Config {
                             "rotate_90" => config.rotate_90 = key,
                             "rotate_180" => config.rotate_180 = key,
                             "rotate_270" => config.rotate_270 = key,
+                            "swap_hold" => config.swap_hold = key,
+                            "toggle" => config.toggle = key,
+                            "garbage" => config.garbage = key,
+                            "cursor_up" => config.cursor_up = key,
+                            "cursor_down" => config.cursor_down = key,
+                            "cursor_left" => config.cursor_left = key,
+                            "cursor_right" => config.cursor_right = key,
+                            option if option.starts_with("p1_") => {
+                                config.players[0].set(&option[3..], key)
+                            }
+                            option if option.starts_with("p2_") => {
+                                config.players[1].set(&option[3..], key)
+                            }
                             "fps" => {
                                 if let Ok(fps) = value.parse() {
                                     config.fps = fps;
                                     config.frame_time = 1000 / fps;
                                 }
                             }
+                            "garbage_delay" => {
+                                if let Ok(delay) = value.trim().parse() {
+                                    config.garbage_delay = delay;
+                                }
+                            }
+                            "stats" => config.stats = value.trim() == "true",
+                            "mode" => {
+                                if let Ok(mode) = value.replace(['\'', '"'], "").parse() {
+                                    config.mode = mode;
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -88,6 +189,55 @@ impl Config {
         Config::default()
     }
 
+    // The guideline unless a rules file sets a preset or options of its own
+    pub fn ruleset() -> Result<Ruleset, String> {
+        let path = Self::folder() + "rules";
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => contents
+                .parse()
+                .map_err(|err| format!(" Invalid ruleset {}\n{}", path, err)),
+            Err(_) => Ok(Ruleset::guideline()),
+        }
+    }
+
+    // Keys and frame rate as they would be written in the config, kept in replays
+    pub fn handling(&self) -> String {
+        let keys = [
+            ("hold", self.hold),
+            ("left", self.left),
+            ("right", self.right),
+            ("soft_drop", self.soft_drop),
+            ("hard_drop", self.hard_drop),
+            ("rotate_90", self.rotate_90),
+            ("rotate_180", self.rotate_180),
+            ("rotate_270", self.rotate_270),
+        ];
+
+        // Unbound keys are left out
+        let mut handling: Vec<String> = keys
+            .iter()
+            .filter(|(_, key)| *key != '\0')
+            .map(|(option, key)| format!("{} = '{}'", option, key))
+            .collect();
+
+        handling.push(format!("fps = {}", self.fps));
+        handling.join("\n")
+    }
+
+    pub fn replay_folder() -> String {
+        Self::folder() + if cfg!(windows) { r"replay\" } else { "replay/" }
+    }
+
+    pub fn puzzle_folder() -> String {
+        Self::folder()
+            + if cfg!(windows) {
+                r"puzzles\"
+            } else {
+                "puzzles/"
+            }
+    }
+
     pub fn folder() -> String {
         if cfg!(windows) {
             if let Ok(home) = std::env::var("USERPROFILE") {
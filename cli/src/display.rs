@@ -1,8 +1,11 @@
-use core::{Board, Colour, Piece};
+use core::{Board, Colour, Piece, PieceStats, PieceType, Point};
 use std::fmt::Display;
 
 const BUFFER_WIDTH: usize = 80;
 const BUFFER_HEIGHT: usize = 24;
+const HOLD_LEFT: usize = 2;
+const PLAY_FIELD_LEFT: usize = HOLD_LEFT + 7 + 3;
+const NEXT_LEFT: usize = PLAY_FIELD_LEFT + Board::WIDTH + 4;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ScreenCell {
@@ -44,12 +47,15 @@ impl Default for ScreenBuffer {
     }
 }
 
+// Clears the screen and moves the cursor to the top left
+pub const CLEAR: &str = "\x1b[2J\x1b[1;1H";
+
 pub fn clear_terminal() {
-    print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+    print!("{}", CLEAR);
 }
 
-fn move_cursor(x: usize, y: usize) {
-    print!("\x1b[{y};{x}H", x = x, y = y);
+fn move_cursor(x: usize, y: usize) -> String {
+    format!("\x1b[{y};{x}H", x = x, y = y)
 }
 
 impl ScreenBuffer {
@@ -69,28 +75,34 @@ impl ScreenBuffer {
         self
     }
 
-    pub fn print(self) {
-        move_cursor(0, 0);
-
-        let s = self
-            .buffer
-            .into_iter()
-            .map(|row| {
-                row.into_iter()
-                    .map(|cell| format!("{}", cell))
-                    .reduce(|acc, v| acc + &v)
-                    .unwrap()
-            })
-            .reduce(|acc, v| format!("{}\n{}", acc, v));
+    pub fn write_stats(&mut self, x: usize, y: usize, stats: PieceStats) -> &mut Self {
+        self.write_string(x, y, "Pieces  Count  Gap", Colour::White);
+
+        for (i, kind) in PieceType::ALL.into_iter().enumerate() {
+            self.write_string(
+                x,
+                y + 2 + i,
+                &format!("{:?}", kind),
+                Piece::new(kind).colour(),
+            )
+            .write_string(
+                x + 8,
+                y + 2 + i,
+                &format!("{:>5}  {:>3}", stats.count(kind), stats.gap(kind)),
+                Colour::White,
+            );
+        }
 
-        print!("{}\n", s.unwrap());
+        self.write_string(
+            x,
+            y + 10,
+            &format!("Drought: {}", stats.drought()),
+            Colour::White,
+        )
     }
-}
-
-impl From<&Board> for ScreenBuffer {
-    fn from(board: &Board) -> Self {
-        let mut buf = ScreenBuffer::default();
 
+    // Draws the hold box, play field and queue of a board with its left edge at x
+    pub fn write_board(&mut self, x: usize, board: &Board) -> &mut Self {
         fn draw_piece(buf: &mut ScreenBuffer, piece: Piece, offset_x: i8, offset_y: i8) {
             let blocks = piece.blocks();
 
@@ -108,38 +120,40 @@ impl From<&Board> for ScreenBuffer {
             }
         }
 
-        const HOLD_LEFT: usize = 2;
+        let hold_left = HOLD_LEFT + x;
+        let play_field_left = PLAY_FIELD_LEFT + x;
+        let next_left = NEXT_LEFT + x;
+
         const HOLD_TOP: usize = 3;
 
-        buf.write_string(HOLD_LEFT, HOLD_TOP - 1, "Hold", Colour::White);
+        self.write_string(hold_left, HOLD_TOP - 1, "Hold", Colour::White);
 
         for y in HOLD_TOP..=(HOLD_TOP + 5) {
-            buf.write(HOLD_LEFT, y, ScreenCell::new('#', Colour::Grey));
-            buf.write(HOLD_LEFT + 7, y, ScreenCell::new('#', Colour::Grey));
+            self.write(hold_left, y, ScreenCell::new('#', Colour::Grey));
+            self.write(hold_left + 7, y, ScreenCell::new('#', Colour::Grey));
         }
 
         if let Some(piece) = board.held() {
-            draw_piece(&mut buf, piece, (HOLD_TOP + 1) as i8, (HOLD_LEFT + 2) as i8);
+            draw_piece(self, piece, (hold_left + 2) as i8, (HOLD_TOP + 1) as i8);
         }
 
         const HOLD_BOTTOM: &str = "######";
 
-        buf.write_string(HOLD_LEFT + 1, HOLD_TOP, HOLD_BOTTOM, Colour::Grey);
-        buf.write_string(HOLD_LEFT + 1, HOLD_TOP + 5, HOLD_BOTTOM, Colour::Grey);
+        self.write_string(hold_left + 1, HOLD_TOP, HOLD_BOTTOM, Colour::Grey);
+        self.write_string(hold_left + 1, HOLD_TOP + 5, HOLD_BOTTOM, Colour::Grey);
 
         const PLAY_FIELD_BOTTOM: &str = "##########";
-        const PLAY_FIELD_LEFT: usize = HOLD_LEFT + 7 + 3;
 
         for y in 2..23 {
-            buf.write(PLAY_FIELD_LEFT - 1, y, ScreenCell::new('#', Colour::Grey));
-            buf.write(
-                PLAY_FIELD_LEFT + Board::WIDTH,
+            self.write(play_field_left - 1, y, ScreenCell::new('#', Colour::Grey));
+            self.write(
+                play_field_left + Board::WIDTH,
                 y,
                 ScreenCell::new('#', Colour::Grey),
             );
         }
 
-        buf.write_string(PLAY_FIELD_LEFT, 22, PLAY_FIELD_BOTTOM, Colour::Grey);
+        self.write_string(play_field_left, 22, PLAY_FIELD_BOTTOM, Colour::Grey);
 
         let blocks = board.blocks();
 
@@ -149,7 +163,7 @@ impl From<&Board> for ScreenBuffer {
                 let y = y.wrapping_sub(23);
 
                 if y < Board::HEIGHT && colour != Colour::None {
-                    buf.write(x + PLAY_FIELD_LEFT, y, ScreenCell::new('@', colour));
+                    self.write(x + play_field_left, y, ScreenCell::new('@', colour));
                 }
             }
         }
@@ -158,35 +172,148 @@ impl From<&Board> for ScreenBuffer {
         let position = board.position();
 
         draw_piece(
-            &mut buf,
+            self,
             piece,
-            position.x() + PLAY_FIELD_LEFT as i8,
+            position.x() + play_field_left as i8,
             position.y().wrapping_sub(23),
         );
 
-        const NEXT_LEFT: usize = PLAY_FIELD_LEFT + Board::WIDTH + 4;
         const NEXT_TOP: usize = 3;
 
-        buf.write_string(NEXT_LEFT, NEXT_TOP - 1, "Next", Colour::White);
+        self.write_string(next_left, NEXT_TOP - 1, "Next", Colour::White);
 
         for y in NEXT_TOP..=(NEXT_TOP + 11) {
-            buf.write(NEXT_LEFT, y, ScreenCell::new('#', Colour::Grey));
-            buf.write(NEXT_LEFT + 7, y, ScreenCell::new('#', Colour::Grey));
+            self.write(next_left, y, ScreenCell::new('#', Colour::Grey));
+            self.write(next_left + 7, y, ScreenCell::new('#', Colour::Grey));
         }
 
         for i in 0..3 {
             draw_piece(
-                &mut buf,
+                self,
                 board.peek(i),
-                (NEXT_LEFT + 2) as i8,
+                (next_left + 2) as i8,
                 (NEXT_TOP + 1 + i * 3) as i8,
             );
         }
 
         const NEXT_BOTTOM: &str = "######";
 
-        buf.write_string(NEXT_LEFT + 1, NEXT_TOP, NEXT_BOTTOM, Colour::Grey);
-        buf.write_string(NEXT_LEFT + 1, NEXT_TOP + 11, NEXT_BOTTOM, Colour::Grey);
+        self.write_string(next_left + 1, NEXT_TOP, NEXT_BOTTOM, Colour::Grey);
+        self.write_string(next_left + 1, NEXT_TOP + 11, NEXT_BOTTOM, Colour::Grey)
+    }
+
+    // Incoming garbage in the gap left of the play field, rows ready to rise are red
+    pub fn write_meter(&mut self, x: usize, pending: u64, ready: u64) -> &mut Self {
+        const METER_BOTTOM: usize = 21;
+        const METER_HEIGHT: u64 = 20;
+
+        for i in 0..pending.min(METER_HEIGHT) {
+            let colour = if i < ready {
+                Colour::Red
+            } else {
+                Colour::Yellow
+            };
+
+            self.write(
+                x + PLAY_FIELD_LEFT - 2,
+                METER_BOTTOM - i as usize,
+                ScreenCell::new('|', colour),
+            );
+        }
+
+        self
+    }
+
+    // Marks a cell of the board, y being a row of the whole board like for Board::block
+    pub fn write_cursor(&mut self, x: usize, y: usize) -> &mut Self {
+        self.write(
+            x + PLAY_FIELD_LEFT,
+            y.wrapping_sub(23),
+            ScreenCell::new('+', Colour::White),
+        )
+    }
+
+    // Empties the play field and the queue of a board drawn at x, for when the game is paused
+    pub fn hide_board(&mut self, x: usize) -> &mut Self {
+        for y in 2..22 {
+            for i in 0..Board::WIDTH {
+                self.write(x + PLAY_FIELD_LEFT + i, y, ScreenCell::default());
+            }
+        }
+
+        for y in 4..14 {
+            for i in 1..7 {
+                self.write(x + NEXT_LEFT + i, y, ScreenCell::default());
+            }
+        }
+
+        self
+    }
+
+    // A word in the middle of the play field of a board drawn at x
+    pub fn write_banner(&mut self, x: usize, text: &str) -> &mut Self {
+        let left = (Board::WIDTH.saturating_sub(text.len()) / 2) + PLAY_FIELD_LEFT + x;
+
+        self.write_string(left, 11, text, Colour::White)
+    }
+
+    // A bar filled up to a fraction of its width
+    pub fn write_progress(&mut self, x: usize, y: usize, width: usize, fraction: f64) -> &mut Self {
+        let filled = ((fraction.clamp(0.0, 1.0) * width as f64) as usize).min(width);
+
+        self.write(x, y, ScreenCell::new('[', Colour::Grey))
+            .write_string(x + 1, y, &"=".repeat(filled), Colour::White)
+            .write_string(x + 1 + filled, y, &"-".repeat(width - filled), Colour::Grey)
+            .write(x + 1 + width, y, ScreenCell::new(']', Colour::Grey))
+    }
+
+    // Outlines where a piece should land, leaving anything already drawn in place
+    pub fn write_target(&mut self, piece: Piece, position: Point) -> &mut Self {
+        let blocks = piece.blocks();
+
+        for y in 0..4 {
+            for x in 0..4 {
+                if blocks[y][x] == Colour::None {
+                    continue;
+                }
+
+                let x = (x as i8 + position.x()) as usize + PLAY_FIELD_LEFT;
+                let y = ((y as i8 + position.y()) as usize).wrapping_sub(23);
+
+                if x < BUFFER_WIDTH && y < BUFFER_HEIGHT && self.buffer[y][x].c == ' ' {
+                    self.buffer[y][x] = ScreenCell::new('.', piece.colour());
+                }
+            }
+        }
+
+        self
+    }
+
+    // What print writes to the terminal, the cursor moved to the top left and then every row
+    pub fn render(self) -> String {
+        let s = self
+            .buffer
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|cell| format!("{}", cell))
+                    .reduce(|acc, v| acc + &v)
+                    .unwrap()
+            })
+            .reduce(|acc, v| format!("{}\n{}", acc, v));
+
+        format!("{}{}\n", move_cursor(0, 0), s.unwrap())
+    }
+
+    pub fn print(self) {
+        print!("{}", self.render());
+    }
+}
+
+impl From<&Board> for ScreenBuffer {
+    fn from(board: &Board) -> Self {
+        let mut buf = ScreenBuffer::default();
+        buf.write_board(0, board);
 
         buf
     }
@@ -0,0 +1,100 @@
+use crate::{config::Config, display, get_input::get_input, time_format};
+use core::{Bag, Colour, Finesse, Game, GameType, Ruleset};
+use display::ScreenBuffer;
+use std::time::Instant;
+
+// Each round deals one piece onto an empty board with a target to place it on
+pub fn train(conf: Config, rules: &Ruleset) {
+    let start = Instant::now();
+    let (mut rounds, mut hits, mut clean) = (0u64, 0u64, 0u64);
+    let mut feedback = (String::from("Place the piece on the target"), Colour::Grey);
+
+    display::clear_terminal();
+
+    'training: loop {
+        let mut game = Game::new(Bag::new(crate::new_seed()), GameType::new_sandbox(), rules);
+        let placements = Finesse::placements(game.piece().kind(), rules);
+        let (target, position, optimal) =
+            placements[(crate::new_seed() % placements.len() as u64) as usize];
+
+        let mut last_update = start.elapsed().as_millis();
+
+        loop {
+            let duration = start.elapsed();
+            let now = duration.as_millis();
+
+            if now == last_update {
+                continue;
+            }
+
+            last_update = now;
+
+            let (mut input, _) = get_input(conf);
+
+            if input.quit {
+                break 'training;
+            }
+
+            // Holding would swap away the piece the target is for
+            input.hold = false;
+
+            if game.tick(input, now).placed() {
+                let placement = game.board().placement().unwrap();
+                let presses = placement.presses();
+
+                rounds += 1;
+
+                feedback = if !placement.covers(target, position) {
+                    (String::from("Missed the target"), Colour::Red)
+                } else if presses > optimal {
+                    hits += 1;
+
+                    let faults = presses - optimal;
+                    let message = format!(
+                        "{} finesse fault{}, {} presses instead of {}",
+                        faults,
+                        if faults == 1 { "" } else { "s" },
+                        presses,
+                        optimal
+                    );
+
+                    (message, Colour::Yellow)
+                } else {
+                    hits += 1;
+                    clean += 1;
+
+                    (format!("Clean, {} presses", presses), Colour::Green)
+                };
+
+                continue 'training;
+            }
+
+            if now.is_multiple_of(conf.frame_time as u128) {
+                let mut buf = ScreenBuffer::from(game.board());
+                let (message, colour) = &feedback;
+
+                buf.write_target(target, position)
+                    .write_string(26, 16, &format!("Par: {}", optimal), Colour::White)
+                    .write_string(
+                        26,
+                        18,
+                        &format!("Clean: {}/{}", clean, rounds),
+                        Colour::White,
+                    )
+                    .write_string(26, 20, &time_format(duration), Colour::White)
+                    .write_string(2, 23, message, *colour);
+
+                buf.print();
+            }
+        }
+    }
+
+    println!("--------------------");
+    println!(" Finesse Training");
+    println!("--------------------");
+    println!(" Pieces: {}", rounds);
+    println!(" On target: {}", hits);
+    println!(" Clean: {}", clean);
+    println!(" {}", time_format(start.elapsed()));
+    println!("--------------------");
+}
@@ -1,4 +1,4 @@
-use crate::config::Config;
+use crate::config::{Config, KeyMap};
 use core::{Input, InputDirection, InputRotation};
 
 #[link(name = "get_key_c", kind = "static")]
@@ -6,7 +6,7 @@ extern "C" {
     fn get_key_c() -> u8;
 }
 
-fn get_key() -> Option<char> {
+pub fn get_key() -> Option<char> {
     let ch = unsafe { get_key_c() } as char;
 
     if ch == '\0' {
@@ -16,24 +16,29 @@ fn get_key() -> Option<char> {
     }
 }
 
-pub fn get_input(conf: Config) -> Input {
+// Pausing is up to the game loop rather than the game, so the key is kept aside from the input
+pub fn get_input(conf: Config) -> (Input, bool) {
     let mut input = Input::default();
+    let mut pause = false;
 
     while let Some(c) = get_key() {
         let c = c.to_ascii_lowercase();
-        update_input(&mut input, c, conf);
+        pause |= update_input(&mut input, c, conf);
     }
 
-    input
+    (input, pause)
 }
 
-pub fn update_input(input: &mut Input, c: char, conf: Config) {
+// True for the pause key, which is not part of the input
+pub fn update_input(input: &mut Input, c: char, conf: Config) -> bool {
     if c == conf.left {
         input.direction = InputDirection::Left;
     } else if c == conf.right {
         input.direction = InputDirection::Right;
     } else if c == conf.quit {
         input.quit = true;
+    } else if c == conf.pause {
+        return true;
     } else if c == conf.hold {
         input.hold = true;
     } else if c == conf.rotate_90 {
@@ -49,4 +54,46 @@ pub fn update_input(input: &mut Input, c: char, conf: Config) {
     } else if c == conf.hard_drop {
         input.hard_drop = true;
     }
+
+    false
+}
+
+// Keys are looked up in both players' key maps, quitting ends the match for both
+pub fn get_versus_input(conf: Config) -> [Input; 2] {
+    let mut inputs = [Input::default(); 2];
+
+    while let Some(c) = get_key() {
+        let c = c.to_ascii_lowercase();
+
+        if c == conf.quit {
+            inputs[0].quit = true;
+            inputs[1].quit = true;
+        }
+
+        for (input, keys) in inputs.iter_mut().zip(conf.players) {
+            update_player_input(input, c, keys);
+        }
+    }
+
+    inputs
+}
+
+fn update_player_input(input: &mut Input, c: char, keys: KeyMap) {
+    if c == keys.left {
+        input.direction = InputDirection::Left;
+    } else if c == keys.right {
+        input.direction = InputDirection::Right;
+    } else if c == keys.hold {
+        input.hold = true;
+    } else if c == keys.rotate_90 {
+        input.rotation = InputRotation::Quarter;
+    } else if c == keys.rotate_180 {
+        input.rotation = InputRotation::TwoQuarter;
+    } else if c == keys.rotate_270 {
+        input.rotation = InputRotation::ThreeQuarter;
+    } else if c == keys.soft_drop {
+        input.soft_drop = true;
+    } else if c == keys.hard_drop {
+        input.hard_drop = true;
+    }
 }
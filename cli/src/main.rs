@@ -1,6 +1,9 @@
 use crate::{config::Config, get_input::get_input};
 use chrono::{Datelike, Timelike, Utc};
-use core::{Bag, Board, Colour, Game, GameType, Input, Recorder, Replay, Score, TickType};
+use core::{
+    Bag, Clock, Colour, Game, GameMode, GameType, Input, Metadata, PieceType, Recorder, Replay,
+    Ruleset, TickType,
+};
 use display::ScreenBuffer;
 use rand::{RngCore, SeedableRng};
 use std::{
@@ -9,9 +12,20 @@ use std::{
     time::{Duration, Instant},
 };
 
+mod analysis;
+mod cast;
 mod config;
 mod display;
+mod finesse;
 mod get_input;
+mod net;
+mod puzzles;
+mod race;
+mod replays;
+mod sandbox;
+mod verify;
+mod versus;
+mod viewer;
 
 fn time_format(duration: Duration) -> String {
     let ms = duration.as_millis() % 1000;
@@ -21,86 +35,234 @@ fn time_format(duration: Duration) -> String {
     format!("Time: {:0w$}:{:0w$}.{:0w_ms$}", m, s, ms, w = 2, w_ms = 3)
 }
 
-fn print_score(score: Score, duration: Duration) {
+fn write_hud(buf: &mut ScreenBuffer, game: &Game, duration: &Duration) {
+    let score = game.score();
+
+    match game.kind().mode() {
+        GameMode::Marathon => {
+            buf.write_string(26, 14, &format!("Level: {}", score.level()), Colour::White);
+        }
+        GameMode::Dig => {
+            let garbage = format!("Garbage: {}", game.garbage_left());
+            buf.write_string(26, 14, &garbage, Colour::White);
+        }
+        GameMode::Survival => {
+            let garbage = format!("Garbage: {}", game.garbage_received());
+            buf.write_string(26, 14, &garbage, Colour::White);
+        }
+        GameMode::Master => {
+            let section = (score.level() / 100 + 1) * 100;
+            let level = format!("Level: {}/{}", score.level(), section.min(999));
+
+            buf.write_string(26, 12, &format!("Grade: {}", game.grade()), Colour::White)
+                .write_string(26, 14, &level, Colour::White);
+        }
+        GameMode::Puzzle => {
+            if let Some((placed, pieces)) = game.puzzle_pieces() {
+                let pieces = format!("Pieces: {}/{}", placed, pieces);
+                buf.write_string(26, 14, &pieces, Colour::White);
+            }
+        }
+        GameMode::Lines | GameMode::Time | GameMode::Sandbox | GameMode::Versus => {}
+    }
+
+    buf.write_string(26, 16, &format!("Score: {}", score.score()), Colour::White)
+        .write_string(26, 18, &format!("Lines: {}", score.lines()), Colour::White)
+        .write_string(26, 20, &time_format(*duration), Colour::White);
+}
+
+fn print_score(game: &Game, duration: Duration) {
+    let score = game.score();
+    let stats = game.stats();
+
     println!(" Game Results");
     println!("--------------------");
+    println!(" Mode: {}", game.kind());
+    println!(" Rules: {}", game.rules().name());
     println!(" Score: {}", score.score());
     println!(" Lines: {}", score.lines());
+
+    match game.kind().mode() {
+        GameMode::Marathon => println!(" Level: {}", score.level()),
+        GameMode::Dig => println!(
+            " Garbage: {}/{}",
+            game.kind().rows() - game.garbage_left(),
+            game.kind().rows()
+        ),
+        GameMode::Survival => println!(" Garbage received: {}", game.garbage_received()),
+        GameMode::Master => {
+            println!(" Level: {}", score.level());
+            println!(" Grade: {}", game.grade());
+        }
+        GameMode::Puzzle => {
+            if let Some((placed, _)) = game.puzzle_pieces() {
+                println!(" Pieces: {}", placed);
+            }
+        }
+        GameMode::Lines | GameMode::Time | GameMode::Sandbox | GameMode::Versus => {}
+    }
+
     println!(" {}", time_format(duration));
+    println!("--------------------");
+    println!(" Pieces: {}", stats.total());
+
+    for kind in PieceType::ALL {
+        let count = stats.count(kind);
+        let share = if stats.total() > 0 {
+            count as f64 * 100.0 / stats.total() as f64
+        } else {
+            0.0
+        };
+
+        println!("   {:?}: {:>4} {:>5.1}%", kind, count, share);
+    }
+
+    println!(" Longest drought: {}", stats.drought());
+
+    let finesse = game.finesse();
+
+    println!(
+        " Finesse faults: {} on {}/{} pieces",
+        finesse.faults(),
+        finesse.faulty(),
+        finesse.judged()
+    );
+}
+
+// Draws the board with a word over it
+fn print_banner(game: &Game, duration: &Duration, text: &str) {
+    let mut buf = ScreenBuffer::from(game.board());
+
+    write_hud(&mut buf, game, duration);
+    buf.write_banner(0, text).print();
+}
+
+// Shows each word for the given milliseconds, keys pressed meanwhile are dropped
+fn countdown(game: &Game, duration: &Duration, words: &[(&str, u64)]) {
+    for (word, ms) in words {
+        print_banner(game, duration, word);
+        std::thread::sleep(Duration::from_millis(*ms));
+
+        while get_input::get_key().is_some() {}
+    }
+}
+
+// Waits for the pause key with the board hidden, then counts down back into the game
+fn pause(conf: Config, game: &Game, duration: &Duration) {
+    let help = format!("Press {} to resume", conf.pause);
+
+    let mut buf = ScreenBuffer::from(game.board());
+
+    write_hud(&mut buf, game, duration);
+    buf.hide_board(0)
+        .write_banner(0, "Paused")
+        .write_string(2, 23, &help, Colour::Grey)
+        .print();
+
+    while get_input::get_key().map(|c| c.to_ascii_lowercase()) != Some(conf.pause) {
+        std::thread::sleep(Duration::from_millis(conf.frame_time.into()));
+    }
+
+    countdown(game, duration, &[("3", 1000), ("2", 1000), ("1", 1000)]);
 }
 
-fn game_loop<InputFn: FnMut(u128) -> Input, DisplayFn: FnMut(&Board, &Score, &Duration)>(
+// Ticks the game by its own clock, which stands still during countdowns and pauses
+fn game_loop<InputFn: FnMut(u128) -> (Input, bool), DisplayFn: FnMut(&Game, &Duration)>(
+    conf: Config,
     input: &mut InputFn,
     display: &mut DisplayFn,
-    frame_time: u128,
     mut game: Game,
-) -> (Score, Duration) {
-    let start = Instant::now();
+) -> (Game, Duration) {
+    let frame_time = conf.frame_time as u128;
+
+    countdown(&game, &Duration::ZERO, &[("Ready", 1000), ("Go", 500)]);
 
-    let mut last_update: u128 = 0;
+    let start = Instant::now();
+    let mut clock = Clock::new();
 
     'game_loop: loop {
-        let duration = start.elapsed();
-        let now = duration.as_millis();
+        let real = start.elapsed().as_millis();
+        let now = clock.time(real);
 
-        if now == last_update {
+        if now == clock.tick() {
             continue;
         }
 
-        let tick = game.tick(input(now), now);
+        let duration = Duration::from_millis(now as u64);
+        let mut paused = false;
 
-        if tick.kind() == TickType::GameOver {
-            break 'game_loop;
+        // Every millisecond is ticked even when drawing falls behind, so replays play back the same
+        while let Some(tick) = clock.next(real) {
+            let (input, pause) = input(tick);
+            paused |= pause;
+
+            if game.tick(input, tick).kind() == TickType::GameOver {
+                break 'game_loop;
+            }
         }
 
-        last_update = now;
+        if paused {
+            clock.pause(real);
+            pause(conf, &game, &duration);
+            clock.resume(start.elapsed().as_millis());
+
+            continue;
+        }
 
         if now % frame_time as u128 == 0 {
-            display(game.board(), &game.score(), &duration);
+            display(&game, &duration);
         }
     }
 
-    (game.score(), start.elapsed())
+    // The game's own clock, which replays are verified against
+    (game, Duration::from_millis(clock.tick() as u64))
 }
 
-fn play_game(conf: Config) -> (Score, Recorder, Duration) {
-    let seed = {
-        let mut seed = [0u8; 8];
-        let mut rng = rand::rngs::SmallRng::from_entropy();
+fn new_seed() -> u64 {
+    let mut seed = [0u8; 8];
+    let mut rng = rand::rngs::SmallRng::from_entropy();
 
-        rng.fill_bytes(&mut seed);
+    rng.fill_bytes(&mut seed);
 
-        u64::from_be_bytes(seed)
-    };
-    let game_type = GameType::new_lines(40);
-    let game = Game::new(Bag::new(seed), game_type);
+    u64::from_be_bytes(seed)
+}
+
+// The board and everything around it as drawn while playing
+fn game_screen(conf: Config, game: &Game, duration: &Duration) -> ScreenBuffer {
+    let mut buf = ScreenBuffer::from(game.board());
+
+    write_hud(&mut buf, game, duration);
+
+    if conf.stats {
+        buf.write_stats(38, 2, game.stats());
+    }
+
+    buf
+}
+
+fn play_game(conf: Config, game_type: GameType, rules: &Ruleset) -> (Game, Recorder, Duration) {
+    let seed = new_seed();
+    let game = Game::new(Bag::new(seed), game_type, rules);
     let mut recorder = Recorder::new(seed, 0, &game);
 
     display::clear_terminal();
 
     let mut input = |now| {
-        let input = get_input(conf);
+        let (input, pause) = get_input(conf);
         recorder.record(input, now);
 
-        input
+        (input, pause)
     };
 
-    let mut display = |board: &Board, score: &Score, duration: &Duration| {
-        ScreenBuffer::from(board)
-            .write_string(26, 16, &format!("Score: {}", score.score()), Colour::White)
-            .write_string(26, 18, &format!("Lines: {}", score.lines()), Colour::White)
-            .write_string(26, 20, &time_format(*duration), Colour::White)
-            .print();
-    };
+    let mut display = |game: &Game, duration: &Duration| game_screen(conf, game, duration).print();
 
-    let (score, duration) = game_loop(&mut input, &mut display, conf.frame_time.into(), game);
+    let (game, duration) = game_loop(conf, &mut input, &mut display, game);
 
-    (score, recorder, duration)
+    (game, recorder, duration)
 }
 
-fn save_replay_prompt(recorder: Recorder, score: Score, duration: u64) {
-    let folder = Config::folder();
-    let replay_folder = folder.clone() + if cfg!(windows) { r"replay\" } else { "replay/" };
+fn save_replay_prompt<RawFn: FnOnce(&str, i64) -> Vec<u8>>(raw: RawFn) {
+    let replay_folder = Config::replay_folder();
 
     print!(" Please enter a name to save replay: ");
     std::io::stdout().flush().unwrap();
@@ -143,10 +305,7 @@ fn save_replay_prompt(recorder: Recorder, score: Score, duration: u64) {
 
                 let end_time = Utc::now().timestamp_millis();
 
-                if let Err(err) = std::fs::write(
-                    file_path.clone(),
-                    recorder.raw(&name, score, duration, end_time),
-                ) {
+                if let Err(err) = std::fs::write(file_path.clone(), raw(&name, end_time)) {
                     println!(" Failed to save to: {}", file_path);
                     println!("{}", err);
                 } else {
@@ -161,10 +320,17 @@ fn save_replay_prompt(recorder: Recorder, score: Score, duration: u64) {
     println!(" Replay discarded");
 }
 
-fn re_play_game(conf: Config, filename: &str) -> (Score, Duration) {
-    let folder = Config::folder();
-    let replay_folder = folder.clone() + if cfg!(windows) { r"replay\" } else { "replay/" };
-    let path = format!("{}{}", replay_folder, filename);
+// A path not found as given is looked for among the saved replays
+fn replay_path(path: &str) -> String {
+    if std::path::Path::new(path).exists() {
+        path.to_string()
+    } else {
+        format!("{}{}", Config::replay_folder(), path)
+    }
+}
+
+fn read_replay(filename: &str) -> Option<Replay> {
+    let path = replay_path(filename);
 
     let buf = match std::fs::read(path.clone()) {
         Ok(contents) => contents,
@@ -174,46 +340,70 @@ fn re_play_game(conf: Config, filename: &str) -> (Score, Duration) {
         }
     };
 
-    let mut recording = Replay::new(buf).unwrap();
-    let mut next_input = recording.next().unwrap();
+    match Replay::new(buf) {
+        Ok(replay) => Some(replay),
+        Err(err) => {
+            println!(" Unable to load replay \"{}\"", path);
+            println!(" {}", err);
+            None
+        }
+    }
+}
 
-    let game = Game::new(Bag::new(recording.seed()), recording.kind());
+// Replays are only played back by the rules they were recorded with, from the replay itself,
+// the configured ones or a preset
+fn replay_rules(replay: &Replay, rules: Ruleset) -> Option<Ruleset> {
+    let presets = Ruleset::PRESETS.map(|name| Ruleset::preset(name).unwrap());
 
-    display::clear_terminal();
+    replay.ruleset().or_else(|| {
+        std::iter::once(rules)
+            .chain(presets)
+            .find(|rules| replay.matches(rules))
+    })
+}
 
-    let mut input = move |now| {
-        let input = get_input(conf);
-        if input.quit {
-            println!("--------------------");
-            println!(" Cancelling replay playback");
-            return input;
-        }
+// What is kept in the replays this client records, besides the ruleset
+fn replay_metadata(conf: Config) -> [(Metadata, String); 3] {
+    [
+        (
+            Metadata::Client,
+            format!("rustris {}", env!("CARGO_PKG_VERSION")),
+        ),
+        (Metadata::Handling, conf.handling()),
+        (
+            Metadata::Platform,
+            format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        ),
+    ]
+}
 
-        let input = if now >= next_input.time {
-            next_input.input
-        } else {
-            Input::default()
-        };
+fn play(conf: Config, game_type: GameType, rules: &Ruleset) {
+    let (game, mut recorder, duration) = match game_type.mode() {
+        GameMode::Sandbox => sandbox::play_sandbox(conf, rules),
+        _ => play_game(conf, game_type, rules),
+    };
 
-        if now >= next_input.time {
-            if let Some(next) = recording.next() {
-                next_input = next;
-            }
-        }
+    for (key, value) in replay_metadata(conf) {
+        recorder.set_metadata(key, value);
+    }
 
-        input
-    };
+    let replay = recorder
+        .clone()
+        .raw("", &game, duration.as_millis() as u64, 0);
 
-    let mut display = |board: &Board, score: &Score, duration: &Duration| {
-        ScreenBuffer::from(board)
-            .write_string(26, 16, &format!("Score: {}", score.score()), Colour::White)
-            .write_string(26, 18, &format!("Lines: {}", score.lines()), Colour::White)
-            .write_string(26, 20, &time_format(*duration), Colour::White)
-            .write_string(26, 22, &format!("Replay: {}", filename), Colour::Grey)
-            .print();
-    };
+    println!("--------------------");
+    print_score(&game, duration);
 
-    game_loop(&mut input, &mut display, conf.frame_time.into(), game)
+    if let Ok(replay) = Replay::new(replay) {
+        println!(" {}", analysis::summary(&replay.analyse(rules)));
+    }
+
+    println!("--------------------");
+    save_replay_prompt(|name, end_time| {
+        recorder.raw(name, &game, duration.as_millis() as u64, end_time)
+    });
+    println!("--------------------");
+    println!(" Thanks for playing");
 }
 
 fn main() {
@@ -221,20 +411,114 @@ fn main() {
     let conf_file = folder.clone() + "config";
     let conf = Config::from_file(&conf_file);
 
-    if let Some(filename) = std::env::args().nth(1) {
-        let (score, duration) = re_play_game(conf, &filename);
+    let rules = match Config::ruleset() {
+        Ok(rules) => rules,
+        Err(err) => return println!("{}", err),
+    };
 
-        println!("--------------------");
-        print_score(score, duration);
-        println!("--------------------");
-    } else {
-        let (score, recorder, duration) = play_game(conf);
-
-        println!("--------------------");
-        print_score(score, duration);
-        println!("--------------------");
-        save_replay_prompt(recorder, score, duration.as_millis() as u64);
-        println!("--------------------");
-        println!(" Thanks for playing");
+    let args: Vec<String> = std::env::args().collect();
+
+    match args.get(1).map(String::as_str) {
+        Some("play") if args.len() > 2 => match args[2..].join(" ").parse::<GameType>() {
+            Ok(game_type) => play(conf, game_type, &rules),
+            Err(err) => println!("{}", err),
+        },
+        Some("play") => play(conf, conf.mode, &rules),
+        Some("replays") => match args.get(2).map(String::as_str) {
+            None => replays::list(&[]),
+            Some("list") => replays::list(&args[3..]),
+            Some("info") if args.len() == 4 => replays::info(&args[3], &rules),
+            Some("rm") if args.len() > 3 => replays::remove(&args[3..]),
+            Some("rename") if args.len() == 5 => replays::rename(&args[3], &args[4]),
+            _ => println!(" Expected replays [list|info <file>|rm <files>|rename <file> <name>]"),
+        },
+        Some("replay") => match (args.get(2).map(String::as_str), args.get(3)) {
+            (Some("export"), Some(file)) => {
+                let format = match args.get(4).map(String::as_str) {
+                    Some("--format") => args.get(5).map_or("", String::as_str),
+                    _ => "json",
+                };
+
+                replays::export(file, format)
+            }
+            (Some("import"), Some(file)) => {
+                replays::import(file, args.get(4).map(String::as_str))
+            }
+            (Some("analyse"), Some(file)) => {
+                let format = match args.get(4).map(String::as_str) {
+                    Some("--format") => args.get(5).map_or("", String::as_str),
+                    _ => "table",
+                };
+
+                analysis::report(file, format, rules)
+            }
+            (Some("render"), Some(file)) => {
+                let output = match args.get(4).map(String::as_str) {
+                    Some("-o") => args.get(5).map(String::as_str),
+                    _ => None,
+                };
+
+                cast::render(conf, file, output, rules)
+            }
+            (Some("note"), Some(file)) if args.len() > 4 => replays::note(file, &args[4..].join(" ")),
+            _ => println!(" Expected replay export <file> [--format json|text], replay import <file> [name], replay analyse <file> [--format table|csv], replay render <file> [-o <file>] or replay note <file> <text>"),
+        },
+        Some("race") => {
+            let fresh = args.iter().any(|arg| arg == "--new-seed");
+            let mut rest = args.iter().skip(2).map(String::as_str).filter(|arg| *arg != "--new-seed");
+
+            match (rest.next(), rest.next()) {
+                (Some(file), None | Some("board")) => race::race(conf, file, race::View::Board, fresh, rules),
+                (Some(file), Some("piece")) => race::race(conf, file, race::View::Piece, fresh, rules),
+                _ => println!(" Expected race <replay> [board|piece] [--new-seed]"),
+            }
+        }
+        Some("versus") => versus::play_versus(conf, new_seed(), &rules),
+        Some("host") => match args.get(2).map(|port| port.parse::<u16>()) {
+            Some(Ok(port)) => net::host(conf, port, &rules),
+            Some(Err(_)) => println!(" Expected a port number"),
+            None => net::host(conf, net::DEFAULT_PORT, &rules),
+        },
+        Some("join") => match args.get(2) {
+            Some(address) => net::join(conf, address, &rules),
+            None => println!(" Expected an address to join"),
+        },
+        Some("rules") => match args.get(2).map(|name| Ruleset::preset(name)) {
+            Some(Ok(preset)) => print!("{}", preset),
+            Some(Err(err)) => println!(" {}", err),
+            None => print!("{}", rules),
+        },
+        Some("verify") => match args.get(2) {
+            Some(path) => verify::verify(path, &rules),
+            None => println!(" Expected a replay file or folder to verify"),
+        },
+        Some("finesse") => finesse::train(conf, &rules),
+        Some("puzzles") => puzzles::list_puzzles(),
+        Some("puzzle") => match args.get(2) {
+            Some(pack) => puzzles::play_puzzle(conf, pack, args.get(3).map(String::as_str), &rules),
+            None => println!(" Expected a puzzle pack name"),
+        },
+        Some(filename) => {
+            let replay = match read_replay(filename) {
+                Some(replay) => replay,
+                None => return,
+            };
+
+            let rules = match replay_rules(&replay, rules) {
+                Some(rules) => rules,
+                None => return println!(" Replay was recorded with a different ruleset"),
+            };
+
+            if replay.kind().mode() == GameMode::Versus {
+                versus::re_play_versus(conf, replay, &rules);
+            } else {
+                let (game, duration) = viewer::view(conf, filename, replay, &rules);
+
+                println!("--------------------");
+                print_score(&game, duration);
+                println!("--------------------");
+            }
+        }
+        None => play(conf, conf.mode, &rules),
     }
 }
@@ -0,0 +1,165 @@
+use crate::{config::Config, display, get_input::get_input, save_replay_prompt, versus};
+use core::{Lockstep, LockstepError, Message, Ruleset};
+use std::{
+    io::Write,
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_PORT: u16 = 4455;
+
+pub fn host(conf: Config, port: u16, rules: &Ruleset) {
+    let listener = match TcpListener::bind(("0.0.0.0", port)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            println!(" Unable to listen on port {}", port);
+            println!("{}", err);
+            return;
+        }
+    };
+
+    println!(" Waiting for another player on port {}", port);
+
+    let mut stream = match listener.accept() {
+        Ok((stream, address)) => {
+            println!(" {} joined", address);
+            stream
+        }
+        Err(err) => {
+            println!(" Unable to accept a connection");
+            println!("{}", err);
+            return;
+        }
+    };
+
+    let lockstep = Lockstep::new(crate::new_seed(), conf.garbage_delay.into(), 0, rules);
+
+    if let Err(err) = stream.write_all(&lockstep.hello().to_bytes()) {
+        println!(" {}", LockstepError::Disconnected);
+        println!("{}", err);
+        return;
+    }
+
+    play_online(conf, stream, lockstep);
+}
+
+pub fn join(conf: Config, address: &str, rules: &Ruleset) {
+    let address = if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    };
+
+    let mut stream = match TcpStream::connect(&address) {
+        Ok(stream) => stream,
+        Err(err) => {
+            println!(" Unable to connect to {}", address);
+            println!("{}", err);
+            return;
+        }
+    };
+
+    match Message::read(&mut stream).and_then(|hello| Lockstep::from_hello(hello, 1, rules)) {
+        Ok(lockstep) => play_online(conf, stream, lockstep),
+        Err(err) => println!(" {}", err),
+    }
+}
+
+// Local inputs are sent once per step while a reader thread hands over the peer's messages
+fn play_online(conf: Config, mut stream: TcpStream, mut lockstep: Lockstep) {
+    let _ = stream.set_nodelay(true);
+
+    let mut reader = match stream.try_clone() {
+        Ok(reader) => reader,
+        Err(err) => {
+            println!(" {}", err);
+            return;
+        }
+    };
+
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || loop {
+        let message = Message::read(&mut reader);
+        let failed = message.is_err();
+
+        if sender.send(message).is_err() || failed {
+            break;
+        }
+    });
+
+    display::clear_terminal();
+
+    let you = Some(lockstep.local_player());
+    let start = Instant::now();
+    let mut steps: u128 = 0;
+    let mut last_frame: u128 = 0;
+
+    let result = 'online: loop {
+        let now = start.elapsed().as_millis();
+
+        if now >= steps * Lockstep::STEP {
+            steps += 1;
+
+            let (input, _) = get_input(conf);
+            let message = lockstep.local(input);
+
+            if stream.write_all(&message.to_bytes()).is_err() {
+                break Err(LockstepError::Disconnected);
+            }
+        }
+
+        // A peer that quits hangs up right after its last input, so play what arrived first
+        let mut error = None;
+
+        while let Ok(message) = receiver.try_recv() {
+            if let Err(err) = message.and_then(|message| lockstep.receive(message)) {
+                error = Some(err);
+                break;
+            }
+        }
+
+        match lockstep.advance() {
+            Ok(attacks) => {
+                for message in attacks {
+                    if stream.write_all(&message.to_bytes()).is_err() {
+                        break 'online Err(LockstepError::Disconnected);
+                    }
+                }
+            }
+            Err(err) => break Err(err),
+        }
+
+        if lockstep.versus().over() {
+            break Ok(());
+        }
+
+        if let Some(err) = error {
+            break Err(err);
+        }
+
+        if now - last_frame >= conf.frame_time as u128 {
+            last_frame = now;
+
+            let duration = Duration::from_millis(lockstep.duration());
+            versus::print_match(lockstep.versus(), &duration, you);
+        }
+    };
+
+    let duration = Duration::from_millis(lockstep.duration());
+
+    versus::print_results(lockstep.versus(), duration);
+
+    if let Err(err) = result {
+        println!(" {}", err);
+    }
+
+    for (key, value) in crate::replay_metadata(conf) {
+        lockstep.set_metadata(key, value);
+    }
+
+    println!("--------------------");
+    save_replay_prompt(|name, end_time| lockstep.raw(name, end_time));
+}
@@ -0,0 +1,155 @@
+use crate::{config::Config, display, get_input::get_input, print_score, write_hud};
+use core::{Colour, Game, Puzzle, PuzzleStatus, Ruleset};
+use display::ScreenBuffer;
+use std::{collections::BTreeSet, path::Path, time::Duration};
+
+const SOLVED_FILE: &str = "solved";
+
+fn puzzle_key(pack: &str, index: usize, puzzle: &Puzzle) -> String {
+    if puzzle.name().is_empty() {
+        format!("{}/#{}", pack, index + 1)
+    } else {
+        format!("{}/{}", pack, puzzle.name())
+    }
+}
+
+fn solved() -> BTreeSet<String> {
+    let path = Config::puzzle_folder() + SOLVED_FILE;
+
+    match std::fs::read_to_string(path) {
+        Ok(contents) => contents.lines().map(str::to_string).collect(),
+        Err(_) => BTreeSet::new(),
+    }
+}
+
+fn mark_solved(key: String) {
+    let mut solved = solved();
+
+    if solved.insert(key) {
+        let path = Config::puzzle_folder() + SOLVED_FILE;
+        let contents: Vec<String> = solved.into_iter().collect();
+
+        if let Err(err) = std::fs::write(&path, contents.join("\n") + "\n") {
+            println!(" Unable to save progress to {}", path);
+            println!("{}", err);
+        }
+    }
+}
+
+fn read_pack(pack: &str) -> Result<Vec<Puzzle>, String> {
+    let path = Config::puzzle_folder() + pack;
+
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) => return Err(format!(" Unable to read puzzle pack {}\n{}", path, err)),
+    };
+
+    match Puzzle::pack(&contents) {
+        Ok(pack) if pack.is_empty() => Err(format!(" No puzzles in {}", path)),
+        Ok(pack) => Ok(pack),
+        Err(err) => Err(format!(" Invalid puzzle pack {}\n{}", path, err)),
+    }
+}
+
+pub fn list_puzzles() {
+    let folder = Config::puzzle_folder();
+
+    let mut packs: Vec<String> = match std::fs::read_dir(&folder) {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name != SOLVED_FILE)
+            .collect(),
+        Err(err) => {
+            println!(" Unable to read puzzles from {}", folder);
+            println!("{}", err);
+            return;
+        }
+    };
+
+    packs.sort();
+
+    let solved = solved();
+
+    for name in packs {
+        println!("--------------------");
+        println!(" {}", name);
+        println!("--------------------");
+
+        match read_pack(&name) {
+            Ok(pack) => {
+                for (i, puzzle) in pack.iter().enumerate() {
+                    let mark = if solved.contains(&puzzle_key(&name, i, puzzle)) {
+                        'x'
+                    } else {
+                        ' '
+                    };
+
+                    println!(
+                        " [{}] {:>3}. {}  {}",
+                        mark,
+                        i + 1,
+                        puzzle.name(),
+                        puzzle.goal()
+                    );
+                }
+            }
+            Err(err) => println!("{}", err),
+        }
+    }
+}
+
+pub fn play_puzzle(conf: Config, pack_name: &str, index: Option<&str>, rules: &Ruleset) {
+    let pack = match read_pack(pack_name) {
+        Ok(pack) => pack,
+        Err(err) => return println!("{}", err),
+    };
+
+    let solved = solved();
+
+    // Without a number the first unsolved puzzle of the pack is picked
+    let index = match index.map(str::parse::<usize>) {
+        Some(Ok(i)) if i >= 1 && i <= pack.len() => i - 1,
+        Some(_) => return println!(" Expected a puzzle number from 1 to {}", pack.len()),
+        None => (0..pack.len())
+            .find(|i| !solved.contains(&puzzle_key(pack_name, *i, &pack[*i])))
+            .unwrap_or(0),
+    };
+
+    let puzzle = &pack[index];
+    let game = Game::from_puzzle(puzzle, rules);
+
+    display::clear_terminal();
+
+    let mut input = |_| get_input(conf);
+
+    let mut display = |game: &Game, duration: &Duration| {
+        let mut buf = ScreenBuffer::from(game.board());
+
+        write_hud(&mut buf, game, duration);
+        buf.write_string(26, 22, &format!("Goal: {}", puzzle.goal()), Colour::Grey);
+
+        if conf.stats {
+            buf.write_stats(38, 2, game.stats());
+        }
+
+        buf.print();
+    };
+
+    let (game, duration) = crate::game_loop(conf, &mut input, &mut display, game);
+
+    println!("--------------------");
+    print_score(&game, duration);
+    println!("--------------------");
+
+    if game.puzzle_status() == Some(PuzzleStatus::Solved) {
+        println!(" Puzzle solved");
+
+        if Path::new(&Config::puzzle_folder()).is_dir() {
+            mark_solved(puzzle_key(pack_name, index, puzzle));
+        }
+    } else {
+        println!(" Puzzle failed");
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{
+    config::Config, display, game_loop, get_input::get_input, new_seed, print_score,
+    replay_metadata, replay_rules, replays, save_replay_prompt, time_format, write_hud,
+};
+use core::{Bag, Colour, Finish, Game, GameMode, Ghost, Recorder, Ruleset};
+use display::ScreenBuffer;
+use std::time::Duration;
+
+// The ghost's board starts just right of the first one's queue, like a versus opponent
+const GHOST_BOARD: usize = 40;
+
+// How the ghost is shown, its whole board next to ours or only its piece on ours
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum View {
+    Board,
+    Piece,
+}
+
+// +1.23s when behind the ghost, -1.23s when ahead
+fn format_delta(delta: i128) -> String {
+    let sign = if delta > 0 { '+' } else { '-' };
+
+    format!("{}{:.2}s", sign, delta.unsigned_abs() as f64 / 1000.0)
+}
+
+fn delta_colour(delta: i128) -> Colour {
+    if delta > 0 {
+        Colour::Red
+    } else {
+        Colour::Green
+    }
+}
+
+// Plays the mode of a saved replay while the replay runs alongside, comparing when lines are reached.
+// The pieces come in the replay's order unless a new seed is asked for
+pub fn race(conf: Config, path: &str, view: View, fresh: bool, rules: Ruleset) {
+    let replay = match replays::load(path) {
+        Ok(replay) => replay,
+        Err(err) => return println!("{}", err),
+    };
+
+    if replay.kind().mode() == GameMode::Versus {
+        return println!(" Only single player replays can be raced");
+    }
+
+    let rules = match replay_rules(&replay, rules) {
+        Some(rules) => rules,
+        None => return println!(" Replay was recorded with a different ruleset"),
+    };
+
+    let name = replay.username().clone();
+    let kind = replay.kind();
+    let seed = if fresh { new_seed() } else { replay.seed() };
+    let mut ghost = Ghost::new(replay, &rules);
+
+    let game = Game::new(Bag::new(seed), kind, &rules);
+    let mut recorder = Recorder::new(seed, 0, &game);
+
+    display::clear_terminal();
+
+    let mut input = |now| {
+        let (input, pause) = get_input(conf);
+        recorder.record(input, now);
+
+        (input, pause)
+    };
+
+    // Lines are seen when drawing, so when they were reached is as close as the frame time
+    let mut reached = (0, 0);
+
+    let mut display = |game: &Game, duration: &Duration| {
+        let now = duration.as_millis();
+        let lines = game.score().lines();
+
+        ghost.advance(now);
+
+        if lines > reached.0 {
+            reached = (lines, now);
+        }
+
+        let mut buf = ScreenBuffer::from(game.board());
+
+        write_hud(&mut buf, game, duration);
+
+        match view {
+            View::Board => {
+                let title = format!("Ghost: {}", name);
+                let lines = format!("Lines: {}", ghost.game().score().lines());
+
+                buf.write_board(GHOST_BOARD, ghost.game().board())
+                    .write_string(GHOST_BOARD + 2, 0, &title, Colour::Grey)
+                    .write_string(GHOST_BOARD + 26, 18, &lines, Colour::Grey);
+            }
+            View::Piece => {
+                let ghost = ghost.game();
+                let lines = format!("Ghost: {}", ghost.score().lines());
+
+                buf.write_target(ghost.piece(), ghost.position())
+                    .write_string(26, 22, &lines, Colour::Grey);
+            }
+        }
+
+        if let Some(delta) = ghost.delta(reached.0, reached.1).filter(|_| reached.0 > 0) {
+            let text = format!("{} at {} lines", format_delta(delta), reached.0);
+            buf.write_string(2, 23, &text, delta_colour(delta));
+        }
+
+        buf.print();
+    };
+
+    let (game, duration) = game_loop(conf, &mut input, &mut display, game);
+
+    println!("--------------------");
+    print_score(&game, duration);
+    println!("--------------------");
+
+    // The end of the game is exact, unlike the live delta
+    match ghost.finish(&game, duration.as_millis()) {
+        Finish::Time(delta) => {
+            let split = (duration.as_millis() as i128 - delta) as u64;
+            let place = if delta > 0 { "behind" } else { "ahead of" };
+
+            println!(" Ghost {}", time_format(Duration::from_millis(split)));
+            println!(
+                " {:.2}s {} the ghost",
+                delta.unsigned_abs() as f64 / 1000.0,
+                place
+            );
+        }
+        Finish::Lines(lines, ghost) => println!(" Lines: {}  Ghost lines: {}", lines, ghost),
+        Finish::Garbage(rows, ghost) => {
+            println!(
+                " Garbage cleared: {}  Ghost garbage cleared: {}",
+                rows, ghost
+            )
+        }
+        Finish::Score(score, ghost) => println!(" Score: {}  Ghost score: {}", score, ghost),
+        Finish::Grade(grade, ghost) => println!(" Grade: {}  Ghost grade: {}", grade, ghost),
+    }
+
+    println!("--------------------");
+
+    for (key, value) in replay_metadata(conf) {
+        recorder.set_metadata(key, value);
+    }
+
+    save_replay_prompt(|name, end_time| {
+        recorder.raw(name, &game, duration.as_millis() as u64, end_time)
+    });
+}
@@ -0,0 +1,293 @@
+use crate::{config::Config, replay_path, replay_rules, time_format};
+use chrono::{TimeZone, Utc};
+use core::{GameMode, GameType, Metadata, Replay, Ruleset};
+use std::{cmp::Reverse, path::Path, time::Duration};
+
+pub fn load(path: &str) -> Result<Replay, String> {
+    let path = replay_path(path);
+    let buf = std::fs::read(&path).map_err(|err| format!(" {}: {}", path, err))?;
+
+    Replay::new(buf).map_err(|err| format!(" Unable to load replay \"{}\"\n {}", path, err))
+}
+
+// Prints a replay as json or text, for diffing and for reading in other tools
+pub fn export(path: &str, format: &str) {
+    let replay = match load(path) {
+        Ok(replay) => replay,
+        Err(err) => return println!("{}", err),
+    };
+
+    match format {
+        "json" => print!("{}", replay.to_json()),
+        "text" => print!("{}", replay.to_text()),
+        _ => println!(" Format must be json or text"),
+    }
+}
+
+// Saves an exported replay among the replays, json is told apart by its opening brace
+pub fn import(path: &str, name: Option<&str>) {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => return println!(" {}: {}", path, err),
+    };
+
+    let replay = if contents.trim_start().starts_with('{') {
+        Replay::from_json(&contents)
+    } else {
+        Replay::from_text(&contents)
+    };
+
+    let replay = match replay {
+        Ok(replay) => replay,
+        Err(err) => return println!(" Unable to import \"{}\"\n {}", path, err),
+    };
+
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => match Path::new(path).file_stem() {
+            Some(stem) => stem.to_string_lossy().to_string(),
+            None => return println!(" Expected a name to save the replay as"),
+        },
+    };
+
+    let replay_folder = Config::replay_folder();
+    let file_path = format!("{}{}", replay_folder, name);
+
+    if let Err(err) = std::fs::create_dir_all(&replay_folder) {
+        return println!(" {}: {}", replay_folder, err);
+    }
+
+    match std::fs::write(&file_path, replay.to_bytes()) {
+        Ok(_) => println!(" Replay saved to: {}", file_path),
+        Err(err) => println!(" {}: {}", file_path, err),
+    }
+}
+
+// Keeps a note with the replay in the same file, \n in the text starts a new line
+pub fn note(path: &str, text: &str) {
+    let mut replay = match load(path) {
+        Ok(replay) => replay,
+        Err(err) => return println!("{}", err),
+    };
+
+    let path = replay_path(path);
+    replay.set_metadata(Metadata::Notes, text.replace("\\n", "\n"));
+
+    match std::fs::write(&path, replay.to_bytes()) {
+        Ok(_) => println!(" Note saved to: {}", path),
+        Err(err) => println!(" {}: {}", path, err),
+    }
+}
+
+const LIST_USAGE: &str = " Expected replays list [--sort date|score|lines|time|mode|user|name] [--reverse] [--mode <mode>] [--user <name>]";
+
+fn date(time_stamp: i64) -> String {
+    match Utc.timestamp_millis_opt(time_stamp).single() {
+        Some(date) => date.format("%Y-%m-%d %H:%M").to_string(),
+        None => String::from("-"),
+    }
+}
+
+fn clock(ms: u64) -> String {
+    time_format(Duration::from_millis(ms))[6..].to_string()
+}
+
+// Every replay in the replay folder by the name it was saved as, files that do not load are left out
+fn saved_replays() -> Result<Vec<(String, Replay)>, String> {
+    let replay_folder = Config::replay_folder();
+    let entries = std::fs::read_dir(&replay_folder)
+        .map_err(|err| format!(" Unable to read replays from {}\n {}", replay_folder, err))?;
+
+    Ok(entries
+        .flatten()
+        .filter_map(|entry| {
+            let replay = Replay::new(std::fs::read(entry.path()).ok()?).ok()?;
+
+            Some((entry.file_name().to_string_lossy().to_string(), replay))
+        })
+        .collect())
+}
+
+// Modes are matched as they are shown, marathon matches every marathon and 40l only 40 lines
+fn mode_matches(kind: GameType, mode: &str) -> bool {
+    let shown = kind.to_string();
+    let name = shown.split_whitespace().next().unwrap_or_default();
+
+    shown.eq_ignore_ascii_case(mode) || name.eq_ignore_ascii_case(mode)
+}
+
+// The saved replays as a table read from their headers, newest first unless sorted otherwise
+pub fn list(options: &[String]) {
+    let mut sort = "date";
+    let mut reverse = false;
+    let mut mode = None;
+    let mut user = None;
+    let mut options = options.iter().map(String::as_str);
+
+    while let Some(option) = options.next() {
+        match option {
+            "--reverse" => reverse = true,
+            "--sort" => sort = options.next().unwrap_or_default(),
+            "--mode" => mode = options.next(),
+            "--user" => user = options.next(),
+            _ => return println!("{}", LIST_USAGE),
+        }
+    }
+
+    let mut replays = match saved_replays() {
+        Ok(replays) => replays,
+        Err(err) => return println!("{}", err),
+    };
+
+    replays.retain(|(_, replay)| {
+        mode.is_none_or(|mode| mode_matches(replay.kind(), mode))
+            && user.is_none_or(|user| replay.username().eq_ignore_ascii_case(user))
+    });
+
+    // Ties keep the order of their names
+    replays.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    match sort {
+        "date" => replays.sort_by_key(|(_, replay)| Reverse(replay.time_stamp())),
+        "score" => replays.sort_by_key(|(_, replay)| Reverse(replay.score().score())),
+        "lines" => replays.sort_by_key(|(_, replay)| Reverse(replay.score().lines())),
+        "time" => replays.sort_by_key(|(_, replay)| replay.duration()),
+        "mode" => replays.sort_by_key(|(_, replay)| {
+            let kind = replay.kind();
+            (kind.mode() as u8, kind.lines(), kind.level())
+        }),
+        "user" => replays.sort_by_key(|(_, replay)| replay.username().to_lowercase()),
+        "name" => {}
+        _ => return println!("{}", LIST_USAGE),
+    }
+
+    if reverse {
+        replays.reverse();
+    }
+
+    if replays.is_empty() {
+        return println!(" No replays found in {}", Config::replay_folder());
+    }
+
+    let rows: Vec<[String; 7]> = replays
+        .iter()
+        .map(|(name, replay)| {
+            [
+                name.clone(),
+                replay.kind().to_string(),
+                replay.username().clone(),
+                replay.score().score().to_string(),
+                replay.score().lines().to_string(),
+                clock(replay.duration()),
+                date(replay.time_stamp()),
+            ]
+        })
+        .collect();
+
+    let header = ["Name", "Mode", "User", "Score", "Lines", "Time", "Date"].map(String::from);
+    let mut widths = [0; 7];
+
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(column, (cell, width))| match column {
+                // Numbers line up on the right
+                3 | 4 => format!("{:>width$}", cell, width = width),
+                _ => format!("{:width$}", cell, width = width),
+            })
+            .collect();
+
+        println!(" {}", cells.join("  ").trim_end());
+    }
+}
+
+// Everything the header and metadata of a replay hold
+pub fn info(path: &str, rules: &Ruleset) {
+    let replay = match load(path) {
+        Ok(replay) => replay,
+        Err(err) => return println!("{}", err),
+    };
+
+    let kind = replay.kind();
+    let rules = match replay_rules(&replay, rules.clone()) {
+        Some(rules) => format!("{} ({:016x})", rules.name(), replay.rules()),
+        None => format!("Unknown ({:016x})", replay.rules()),
+    };
+
+    println!(" File:     {}", replay_path(path));
+    println!(" User:     {}", replay.username());
+    println!(" Mode:     {}", kind);
+    println!(" Score:    {}", replay.score().score());
+    println!(" Lines:    {}", replay.score().lines());
+    println!(" Level:    {}", replay.score().level());
+
+    if kind.mode() == GameMode::Master {
+        println!(" Grade:    {}", replay.grade());
+    }
+
+    println!(" Time:     {}", clock(replay.duration()));
+    println!(" Date:     {}", date(replay.time_stamp()));
+    println!(" Seed:     {}", replay.seed());
+    println!(" Rules:    {}", rules);
+    println!(" Version:  {}", replay.version());
+
+    // The ruleset is shown by name above, the whole of it is in the export
+    for (key, value) in replay.metadata_entries() {
+        if *key == Metadata::Ruleset {
+            continue;
+        }
+
+        let mut lines = value.lines();
+        let label = format!("{}:", key.name());
+
+        println!(" {:9} {}", label, lines.next().unwrap_or_default());
+
+        for line in lines {
+            println!("           {}", line);
+        }
+    }
+}
+
+// Deletes saved replays, refusing files that are not replays
+pub fn remove(paths: &[String]) {
+    for path in paths {
+        if let Err(err) = load(path) {
+            println!("{}", err);
+            continue;
+        }
+
+        let path = replay_path(path);
+
+        match std::fs::remove_file(&path) {
+            Ok(_) => println!(" Removed {}", path),
+            Err(err) => println!(" {}: {}", path, err),
+        }
+    }
+}
+
+// Renames a replay within the folder it is in, never over another file
+pub fn rename(path: &str, name: &str) {
+    if let Err(err) = load(path) {
+        return println!("{}", err);
+    }
+
+    let path = replay_path(path);
+    let new_path = Path::new(&path).with_file_name(name);
+
+    if name.contains(['/', '\\']) || new_path.exists() {
+        return println!(" Unable to rename to \"{}\", choose another name", name);
+    }
+
+    match std::fs::rename(&path, &new_path) {
+        Ok(_) => println!(" Renamed {} to {}", path, new_path.display()),
+        Err(err) => println!(" {}: {}", path, err),
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{config::Config, display, get_input, write_hud};
+use core::{Bag, Board, Colour, Edit, Game, GameType, Input, PieceType, Recorder, Ruleset};
+use display::ScreenBuffer;
+use std::{cell::Cell, time::Duration};
+
+// Only the 20 rows inside the play field can be edited
+const TOP_ROW: usize = Board::HEIGHT - 20;
+
+// Sandbox keys move the cursor or turn into edits, anything else is read as usual
+fn sandbox_input(conf: Config, cursor: &Cell<(usize, usize)>) -> (Input, bool) {
+    let mut input = Input::default();
+    let mut pause = false;
+
+    while let Some(c) = get_input::get_key() {
+        let c = c.to_ascii_lowercase();
+        let (x, y) = cursor.get();
+
+        if c == conf.cursor_up {
+            cursor.set((x, y.saturating_sub(1).max(TOP_ROW)));
+        } else if c == conf.cursor_down {
+            cursor.set((x, (y + 1).min(Board::HEIGHT - 1)));
+        } else if c == conf.cursor_left {
+            cursor.set((x.saturating_sub(1), y));
+        } else if c == conf.cursor_right {
+            cursor.set(((x + 1).min(Board::WIDTH - 1), y));
+        } else if c == conf.toggle {
+            input.edit = Some(Edit::Toggle(x, y));
+        } else if c == conf.swap_hold {
+            input.edit = Some(Edit::SwapHold);
+        } else if c == conf.garbage {
+            input.edit = Some(Edit::Garbage);
+        } else if let Some(kind) = c
+            .to_digit(10)
+            .and_then(|digit| PieceType::ALL.get((digit as usize).checked_sub(1)?))
+        {
+            input.edit = Some(Edit::Piece(*kind));
+        } else {
+            pause |= get_input::update_input(&mut input, c, conf);
+        }
+    }
+
+    (input, pause)
+}
+
+pub fn play_sandbox(conf: Config, rules: &Ruleset) -> (Game, Recorder, Duration) {
+    let seed = crate::new_seed();
+    let game = Game::new(Bag::new(seed), GameType::new_sandbox(), rules);
+    let mut recorder = Recorder::new(seed, 0, &game);
+    let cursor = Cell::new((Board::WIDTH / 2, Board::HEIGHT - 1));
+
+    display::clear_terminal();
+
+    let mut input = |now| {
+        let (input, pause) = sandbox_input(conf, &cursor);
+        recorder.record(input, now);
+
+        (input, pause)
+    };
+
+    let help = format!(
+        "1-7: I J L O S T Z  {}: swap hold  {}: toggle  {}: garbage",
+        conf.swap_hold, conf.toggle, conf.garbage
+    );
+
+    let mut display = |game: &Game, duration: &Duration| {
+        let mut buf = ScreenBuffer::from(game.board());
+        let (x, y) = cursor.get();
+
+        write_hud(&mut buf, game, duration);
+        buf.write_cursor(x, y)
+            .write_string(2, 23, &help, Colour::Grey);
+
+        if conf.stats {
+            buf.write_stats(38, 2, game.stats());
+        }
+
+        buf.print();
+    };
+
+    let (game, duration) = crate::game_loop(conf, &mut input, &mut display, game);
+
+    (game, recorder, duration)
+}
@@ -0,0 +1,89 @@
+use crate::{replay_path, replay_rules, time_format};
+use core::{Replay, Ruleset};
+use std::{path::Path, time::Duration};
+
+// Replays in a folder, or the one file
+fn replay_files(path: &str) -> Result<Vec<String>, String> {
+    let path = replay_path(path);
+
+    if !Path::new(&path).is_dir() {
+        return Ok(vec![path]);
+    }
+
+    let entries = std::fs::read_dir(&path).map_err(|err| format!(" {}: {}", path, err))?;
+    let mut files: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.path().to_string_lossy().to_string())
+        .collect();
+
+    files.sort();
+
+    Ok(files)
+}
+
+// Whether the replay holds up, printing what does not
+fn verify_file(file: &str, rules: &Ruleset) -> bool {
+    let replay = match std::fs::read(file)
+        .map_err(|err| err.to_string())
+        .and_then(|buf| Replay::new(buf).map_err(|err| err.to_string()))
+    {
+        Ok(replay) => replay,
+        Err(err) => {
+            println!(" FAIL {}", file);
+            println!("      {}", err);
+            return false;
+        }
+    };
+
+    // Checked against the configured rules when no known ruleset matches, which reports the mismatch
+    let rules = replay_rules(&replay, rules.clone()).unwrap_or_else(|| rules.clone());
+    let verification = replay.verify(&rules);
+
+    if verification.valid() {
+        println!(
+            " OK   {}  {}  Score: {}  Lines: {}  {}",
+            file,
+            replay.username(),
+            replay.score().score(),
+            replay.score().lines(),
+            time_format(Duration::from_millis(replay.duration()))
+        );
+    } else {
+        println!(" FAIL {}", file);
+
+        for mismatch in verification.mismatches() {
+            println!("      {}", mismatch);
+        }
+    }
+
+    verification.valid()
+}
+
+// Plays back every replay at a path and exits with an error if any of them does not hold up
+pub fn verify(path: &str, rules: &Ruleset) {
+    let files = match replay_files(path) {
+        Ok(files) => files,
+        Err(err) => return println!("{}", err),
+    };
+
+    if files.is_empty() {
+        return println!(" No replays found in {}", path);
+    }
+
+    let failed = files
+        .iter()
+        .filter(|file| !verify_file(file, rules))
+        .count();
+
+    println!("--------------------");
+    println!(
+        " {} of {} replays verified",
+        files.len() - failed,
+        files.len()
+    );
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{
+    config::Config,
+    display,
+    get_input::{get_input, get_versus_input},
+    time_format,
+};
+use core::{AttackTable, Bag, Board, Colour, Input, Match, Replay, Ruleset};
+use display::ScreenBuffer;
+use std::time::{Duration, Instant};
+
+// The second board starts just right of the first one's queue
+const SECOND_BOARD: usize = 40;
+
+// Draws both boards, marking the player on this side of an online match
+pub fn print_match(versus: &Match, duration: &Duration, you: Option<usize>) {
+    let mut buf = ScreenBuffer::default();
+    let now = duration.as_millis();
+
+    for (player, x) in [(0, 0), (1, SECOND_BOARD)] {
+        let game = versus.player(player);
+        let queue = versus.queue(player);
+
+        let name = match you {
+            Some(you) if you == player => format!("Player {} (you)", player + 1),
+            _ => format!("Player {}", player + 1),
+        };
+
+        buf.write_board(x, game.board())
+            .write_meter(x, queue.pending(), queue.ready(now));
+
+        buf.write_string(x + 2, 0, &name, Colour::White)
+            .write_string(
+                x + 26,
+                16,
+                &format!("Sent: {}", versus.sent(player)),
+                Colour::White,
+            )
+            .write_string(
+                x + 26,
+                18,
+                &format!("Lines: {}", game.score().lines()),
+                Colour::White,
+            );
+    }
+
+    buf.write_string(2, 23, &time_format(*duration), Colour::White)
+        .print();
+}
+
+pub fn print_results(versus: &Match, duration: Duration) {
+    println!("--------------------");
+    println!(" Versus Results");
+    println!("--------------------");
+
+    for player in 0..2 {
+        let game = versus.player(player);
+
+        println!(
+            " Player {}  Lines: {}  Sent: {}",
+            player + 1,
+            game.score().lines(),
+            versus.sent(player)
+        );
+    }
+
+    println!(" {}", time_format(duration));
+    println!("--------------------");
+
+    match versus.winner() {
+        Some(player) => println!(" Player {} wins", player + 1),
+        None => println!(" No winner"),
+    }
+}
+
+pub fn play_versus(conf: Config, seed: u64, rules: &Ruleset) {
+    let boards = [Board::new(Bag::new(seed)), Board::new(Bag::new(seed))];
+    let delay = conf.garbage_delay.into();
+    let mut versus = Match::with_rules(boards, AttackTable::default(), delay, rules);
+
+    display::clear_terminal();
+
+    let start = Instant::now();
+    let mut last_update: u128 = 0;
+
+    while !versus.over() {
+        let duration = start.elapsed();
+        let now = duration.as_millis();
+
+        if now == last_update {
+            continue;
+        }
+
+        versus.tick(get_versus_input(conf), now);
+
+        last_update = now;
+
+        if now.is_multiple_of(conf.frame_time as u128) {
+            print_match(&versus, &duration, None);
+        }
+    }
+
+    print_results(&versus, start.elapsed());
+}
+
+// Plays both recorded players back, every millisecond is simulated even when drawing falls behind
+pub fn re_play_versus(conf: Config, mut replay: Replay, rules: &Ruleset) {
+    let mut versus = Match::from_replay(&replay, rules);
+    let mut frames = [replay.next(), replay.next_opponent()];
+
+    display::clear_terminal();
+
+    let start = Instant::now();
+    let mut tick: u128 = 0;
+    let mut last_frame: u128 = 0;
+
+    while !versus.over() && tick < replay.duration() as u128 {
+        let now = start.elapsed().as_millis();
+        let (input, _) = get_input(conf);
+
+        if input.quit {
+            println!("--------------------");
+            println!(" Cancelling replay playback");
+            break;
+        }
+
+        while tick < now && !versus.over() {
+            tick += 1;
+
+            let mut inputs = [Input::default(); 2];
+
+            for player in 0..2 {
+                // The first frame is recorded at 0, before the first tick
+                while let Some(frame) = frames[player].filter(|frame| frame.time <= tick) {
+                    inputs[player] = inputs[player].merge(frame.input);
+                    frames[player] = match player {
+                        0 => replay.next(),
+                        _ => replay.next_opponent(),
+                    };
+                }
+            }
+
+            versus.tick(inputs, tick);
+        }
+
+        if now - last_frame >= conf.frame_time as u128 {
+            last_frame = now;
+            print_match(&versus, &Duration::from_millis(tick as u64), None);
+        }
+    }
+
+    print_results(&versus, Duration::from_millis(tick as u64));
+}
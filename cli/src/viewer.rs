@@ -0,0 +1,171 @@
+use crate::{config::Config, display, get_input::get_key, time_format, write_hud};
+use core::{Colour, Game, Playback, Replay, Ruleset};
+use display::ScreenBuffer;
+use std::time::{Duration, Instant};
+
+const SPEEDS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+// Milliseconds skipped by the rewind and fast forward keys
+const SKIP: u128 = 5000;
+
+const HELP: &str = "p pause  ,/. step  -/+ speed  [/] 5s  g go to  q quit";
+
+// Where to jump, #12 for the twelfth piece or a time like 1:23.5 or 83.5
+enum Target {
+    Piece(u64),
+    Time(u128),
+}
+
+fn parse_target(text: &str) -> Option<Target> {
+    if let Some(piece) = text.strip_prefix('#') {
+        return piece.parse().ok().map(Target::Piece);
+    }
+
+    let (minutes, seconds) = match text.split_once(':') {
+        Some((minutes, seconds)) => (minutes.parse::<u64>().ok()?, seconds),
+        None => (0, text),
+    };
+
+    let seconds: f64 = seconds.parse().ok()?;
+
+    if seconds.is_sign_negative() || !seconds.is_finite() {
+        return None;
+    }
+
+    Some(Target::Time(
+        (minutes * 60_000) as u128 + (seconds * 1000.0) as u128,
+    ))
+}
+
+fn draw(conf: Config, filename: &str, playback: &Playback, status: &str, prompt: Option<&str>) {
+    let game = playback.game();
+    let duration = Duration::from_millis(playback.tick() as u64);
+    let mut buf = ScreenBuffer::from(game.board());
+
+    write_hud(&mut buf, game, &duration);
+
+    if conf.stats {
+        buf.write_stats(38, 2, game.stats());
+    }
+
+    let fraction = playback.tick() as f64 / playback.duration().max(1) as f64;
+    let clock = |ms: u128| time_format(Duration::from_millis(ms as u64))[6..].to_string();
+    let progress = format!(
+        "{} / {}  Piece {}  {}",
+        clock(playback.tick()),
+        clock(playback.duration()),
+        playback.pieces() + 1,
+        status
+    );
+
+    match prompt {
+        Some(text) => buf.write_string(
+            2,
+            0,
+            &format!("Go to (m:ss or #piece): {}", text),
+            Colour::White,
+        ),
+        None => buf.write_string(2, 0, HELP, Colour::Grey),
+    };
+
+    buf.write_string(26, 22, &format!("Replay: {}", filename), Colour::Grey)
+        .write_progress(2, 23, 30, fraction)
+        .write_string(35, 23, &progress, Colour::White)
+        .print();
+}
+
+// Plays a replay back with pausing, stepping, speed control and seeking, until quit
+pub fn view(conf: Config, filename: &str, replay: Replay, rules: &Ruleset) -> (Game, Duration) {
+    let frame_time = conf.frame_time as u128;
+    let mut playback = Playback::new(replay, rules);
+
+    let mut speed = SPEEDS.iter().position(|speed| *speed == 1.0).unwrap();
+    let mut paused = false;
+    let mut prompt: Option<String> = None;
+
+    // Replay time to play up to, kept fractional for the slower speeds
+    let mut position = 0.0;
+    let mut last_update = Instant::now();
+
+    display::clear_terminal();
+
+    'viewer: loop {
+        let mut target = None;
+
+        while let Some(c) = get_key() {
+            if let Some(text) = prompt.as_mut() {
+                match c {
+                    '\n' | '\r' => {
+                        target = parse_target(text);
+                        prompt = None;
+                    }
+                    '\x1b' => prompt = None,
+                    '\x08' | '\x7f' => {
+                        text.pop();
+                    }
+                    c => text.push(c),
+                }
+
+                continue;
+            }
+
+            let tick = playback.tick();
+
+            match c.to_ascii_lowercase() {
+                c if c == conf.quit => break 'viewer,
+                c if c == conf.pause || c == ' ' => paused = !paused,
+                '.' => {
+                    paused = true;
+                    target = Some(Target::Time(tick + frame_time));
+                }
+                ',' => {
+                    paused = true;
+                    target = Some(Target::Time(tick.saturating_sub(frame_time)));
+                }
+                '+' | '=' => speed = (speed + 1).min(SPEEDS.len() - 1),
+                '-' | '_' => speed = speed.saturating_sub(1),
+                ']' => target = Some(Target::Time(tick + SKIP)),
+                '[' => target = Some(Target::Time(tick.saturating_sub(SKIP))),
+                'g' => {
+                    paused = true;
+                    prompt = Some(String::new());
+                }
+                _ => {}
+            }
+        }
+
+        let elapsed = last_update.elapsed();
+        last_update = Instant::now();
+
+        match target {
+            Some(Target::Time(tick)) => playback.seek(tick),
+            Some(Target::Piece(piece)) => playback.seek_piece(piece),
+            None if !paused => {
+                position += elapsed.as_secs_f64() * 1000.0 * SPEEDS[speed];
+                playback.seek(position as u128);
+            }
+            None => {}
+        }
+
+        // Seeking moves the position and the end of the game stops it
+        if target.is_some() || playback.over() {
+            position = playback.tick() as f64;
+        }
+
+        let status = if playback.over() {
+            String::from("End")
+        } else if paused {
+            String::from("Paused")
+        } else {
+            format!("{}x", SPEEDS[speed])
+        };
+
+        draw(conf, filename, &playback, &status, prompt.as_deref());
+
+        std::thread::sleep(Duration::from_millis(frame_time as u64));
+    }
+
+    let duration = Duration::from_millis(playback.tick() as u64);
+
+    (playback.game().clone(), duration)
+}
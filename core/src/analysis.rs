@@ -0,0 +1,158 @@
+use crate::{Input, InputDirection, InputRotation, PieceType, Replay, Ruleset, Simulator};
+
+// A placed piece, timed from when the one before it locked to when it locked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PieceTime {
+    pub piece: PieceType,
+    pub start: u128,
+    pub end: u128,
+    pub keys: u64,
+    pub lines: u8,
+    pub attack: u64,
+}
+
+impl PieceTime {
+    // Milliseconds the piece took
+    pub fn time(&self) -> u128 {
+        self.end - self.start
+    }
+}
+
+// Speed and efficiency of a replay, from playing its inputs back
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analysis {
+    duration: u128,
+    keys: u64,
+    lines: u64,
+    attack: u64,
+    pieces: Vec<PieceTime>,
+}
+
+// Keys pressed for an input, each action once and edits not at all
+fn presses(input: &Input) -> u64 {
+    [
+        input.hold,
+        input.hard_drop,
+        input.soft_drop,
+        input.rotation != InputRotation::None,
+        input.direction != InputDirection::None,
+    ]
+    .into_iter()
+    .filter(|pressed| *pressed)
+    .count() as u64
+}
+
+impl Analysis {
+    // Milliseconds played
+    pub fn duration(&self) -> u128 {
+        self.duration
+    }
+
+    pub fn keys(&self) -> u64 {
+        self.keys
+    }
+
+    pub fn lines(&self) -> u64 {
+        self.lines
+    }
+
+    // Rows of garbage the clears would have sent
+    pub fn attack(&self) -> u64 {
+        self.attack
+    }
+
+    pub fn pieces(&self) -> &[PieceTime] {
+        &self.pieces
+    }
+
+    fn per_minute(&self, count: u64) -> f64 {
+        match self.duration {
+            0 => 0.0,
+            duration => count as f64 * 60_000.0 / duration as f64,
+        }
+    }
+
+    // Pieces per second
+    pub fn pps(&self) -> f64 {
+        self.per_minute(self.pieces.len() as u64) / 60.0
+    }
+
+    // Keys per piece
+    pub fn kpp(&self) -> f64 {
+        match self.pieces.len() {
+            0 => 0.0,
+            pieces => self.keys as f64 / pieces as f64,
+        }
+    }
+
+    // Lines per minute
+    pub fn lpm(&self) -> f64 {
+        self.per_minute(self.lines)
+    }
+
+    // Attack per minute
+    pub fn apm(&self) -> f64 {
+        self.per_minute(self.attack)
+    }
+
+    // Indices of the pieces that took longest, slowest first and the earlier of a tie first
+    pub fn slowest(&self, count: usize) -> Vec<usize> {
+        let mut slowest: Vec<usize> = (0..self.pieces.len()).collect();
+        slowest.sort_by_key(|i| std::cmp::Reverse(self.pieces[*i].time()));
+        slowest.truncate(count);
+
+        slowest
+    }
+}
+
+impl Replay {
+    // Plays the inputs back timing every piece, versus replays without the garbage they received
+    pub fn analyse(&self, rules: &Ruleset) -> Analysis {
+        let mut simulator = Simulator::new(self.clone(), rules);
+        let mut pieces = Vec::new();
+        let (mut start, mut keys, mut total_keys, mut sent) = (0, 0, 0, 0);
+
+        while simulator.tick() < self.duration() as u128 {
+            let res = match simulator.step() {
+                Some(res) => res,
+                None => break,
+            };
+
+            let tick = simulator.tick();
+            let game = simulator.game();
+
+            // Frames sharing a tick are merged into one input, every key pressed in them still counts
+            keys += simulator
+                .taken()
+                .iter()
+                .map(|frame| presses(&frame.input))
+                .sum::<u64>();
+
+            if res.placed() {
+                pieces.push(PieceTime {
+                    piece: res.piece(),
+                    start,
+                    end: tick,
+                    keys,
+                    lines: res.lines(),
+                    attack: game.attack().sent() - sent,
+                });
+
+                start = tick;
+                total_keys += keys;
+                keys = 0;
+                sent = game.attack().sent();
+            }
+        }
+
+        let game = simulator.game();
+
+        Analysis {
+            duration: simulator.tick(),
+            keys: total_keys + keys,
+            lines: game.score().lines(),
+            attack: game.attack().sent(),
+            pieces,
+        }
+    }
+}
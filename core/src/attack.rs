@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+
+use crate::{
+    board::{TickResult, TickType},
+    piece::PieceType,
+};
+
+// Rows sent for each kind of clear, indexed by lines cleared
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttackTable {
+    pub clears: [u64; 5],
+    pub t_spins: [u64; 4],
+    pub minis: [u64; 3],
+    pub back_to_back: u64,
+    // Extra rows by clears in a row, the last entry is used for longer combos
+    pub combos: Vec<u64>,
+    pub perfect_clear: u64,
+}
+
+impl Default for AttackTable {
+    fn default() -> Self {
+        Self {
+            clears: [0, 0, 1, 2, 4],
+            t_spins: [0, 2, 4, 6],
+            minis: [0, 0, 1],
+            back_to_back: 1,
+            combos: vec![0, 0, 1, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5],
+            perfect_clear: 10,
+        }
+    }
+}
+
+// Turns the placements of one player into outgoing rows
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attack {
+    table: AttackTable,
+    combo: Option<usize>,
+    back_to_back: bool,
+    last: u64,
+    sent: u64,
+}
+
+impl Default for Attack {
+    fn default() -> Self {
+        Self::new(AttackTable::default())
+    }
+}
+
+impl Attack {
+    pub fn new(table: AttackTable) -> Self {
+        Self {
+            table,
+            combo: None,
+            back_to_back: false,
+            last: 0,
+            sent: 0,
+        }
+    }
+
+    // Rows sent by this placement, 0 for ticks that did not lock a piece
+    pub fn update(&mut self, res: TickResult, perfect_clear: bool) -> u64 {
+        if !res.placed() || res.kind() == TickType::GameOver {
+            return 0;
+        }
+
+        let lines = res.lines() as usize;
+
+        if lines == 0 {
+            self.combo = None;
+            self.last = 0;
+
+            return 0;
+        }
+
+        let t_spin = res.kind() == TickType::Spin && res.piece() == PieceType::T;
+
+        let mut rows = match (t_spin, res.mini()) {
+            (true, true) => self.table.minis[lines.min(2)],
+            (true, false) => self.table.t_spins[lines],
+            (false, _) => self.table.clears[lines],
+        };
+
+        // Tetrises and T-spins keep back to back going, any other clear breaks it
+        let difficult = t_spin || lines == 4;
+
+        if difficult && self.back_to_back {
+            rows += self.table.back_to_back;
+        }
+
+        self.back_to_back = difficult;
+
+        let combo = self.combo.map_or(0, |combo| combo + 1);
+        self.combo = Some(combo);
+
+        if let Some(bonus) = self.table.combos.get(combo).or(self.table.combos.last()) {
+            rows += bonus;
+        }
+
+        if perfect_clear {
+            rows += self.table.perfect_clear;
+        }
+
+        self.last = rows;
+        self.sent += rows;
+
+        rows
+    }
+
+    // Clears in a row so far, 0 for the first clear
+    pub fn combo(&self) -> Option<usize> {
+        self.combo
+    }
+
+    pub fn back_to_back(&self) -> bool {
+        self.back_to_back
+    }
+
+    // Rows sent by the most recent placement
+    pub fn last(&self) -> u64 {
+        self.last
+    }
+
+    // Rows sent in total
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+}
+
+// Rows waiting to be pushed onto a board, each batch may only enter once its delay has passed
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GarbageQueue {
+    incoming: VecDeque<(u64, u128)>,
+    delay: u128,
+}
+
+impl GarbageQueue {
+    pub fn new(delay: u128) -> Self {
+        Self {
+            incoming: VecDeque::new(),
+            delay,
+        }
+    }
+
+    pub fn push(&mut self, rows: u64, tick: u128) {
+        if rows > 0 {
+            self.incoming.push_back((rows, tick + self.delay));
+        }
+    }
+
+    // Outgoing rows cancel the oldest incoming ones first, returns the rows left to send
+    pub fn cancel(&mut self, mut rows: u64) -> u64 {
+        while rows > 0 {
+            match self.incoming.front_mut() {
+                Some((incoming, _)) if *incoming > rows => {
+                    *incoming -= rows;
+                    rows = 0;
+                }
+                Some((incoming, _)) => {
+                    rows -= *incoming;
+                    self.incoming.pop_front();
+                }
+                None => break,
+            }
+        }
+
+        rows
+    }
+
+    // Removes and returns all rows whose delay has passed
+    pub fn take(&mut self, tick: u128) -> u64 {
+        let mut rows = 0;
+
+        while let Some((incoming, _)) = self.incoming.front().filter(|(_, at)| *at <= tick) {
+            rows += incoming;
+            self.incoming.pop_front();
+        }
+
+        rows
+    }
+
+    pub fn pending(&self) -> u64 {
+        self.incoming.iter().map(|(rows, _)| rows).sum()
+    }
+
+    pub fn ready(&self, tick: u128) -> u64 {
+        self.incoming
+            .iter()
+            .filter(|(_, at)| *at <= tick)
+            .map(|(rows, _)| rows)
+            .sum()
+    }
+
+    pub fn delay(&self) -> u128 {
+        self.delay
+    }
+}
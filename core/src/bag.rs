@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use crate::piece::PieceType;
 use rand::prelude::SliceRandom;
 use rand::rngs::SmallRng;
@@ -11,6 +13,7 @@ pub struct Bag {
     rng: SmallRng,
     seed: u64,
     pieces: [PieceType; 14],
+    queue: VecDeque<PieceType>,
 }
 
 const KINDS: [PieceType; 7] = [
@@ -41,10 +44,15 @@ impl Bag {
                 bag1[0], bag1[1], bag1[2], bag1[3], bag1[4], bag1[5], bag1[6], bag2[0], bag2[1],
                 bag2[2], bag2[3], bag2[4], bag2[5], bag2[6],
             ],
+            queue: VecDeque::new(),
         }
     }
 
     pub fn next(&mut self) -> PieceType {
+        if let Some(kind) = self.queue.pop_front() {
+            return kind;
+        }
+
         let kind = self.pieces[self.i % 7];
 
         self.i += 1;
@@ -67,7 +75,24 @@ impl Bag {
         kind
     }
 
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     pub fn peek(&self, i: usize) -> PieceType {
-        self.pieces[(self.i + i) % 7]
+        match self.queue.get(i) {
+            Some(kind) => *kind,
+            None => self.pieces[(self.i + i - self.queue.len()) % 7],
+        }
+    }
+}
+
+impl Bag {
+    // Deals the given pieces in order before falling back to the shuffled bags
+    pub fn from_queue(queue: &[PieceType], seed: u64) -> Self {
+        Self {
+            queue: queue.iter().copied().collect(),
+            ..Self::new(seed)
+        }
     }
 }
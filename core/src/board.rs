@@ -3,14 +3,19 @@ use wasm_bindgen::prelude::wasm_bindgen;
 use crate::{
     bag::Bag,
     colour::Colour,
+    finesse::Placement,
     input::{Input, InputDirection, InputRotation},
-    kicks::{I_KICKS, KICKS},
+    kicks::{BASIC_KICKS, I_KICKS, KICKS, NO_KICKS},
     piece::{Piece, PieceType},
     point::Point,
+    ruleset::{Kicks, Ruleset},
+    stats::PieceStats,
 };
 
 const BOARD_WIDTH: usize = 10;
 const BOARD_HEIGHT: usize = 45;
+const GRAVITY: u128 = 500;
+const LOCK_SAMPLES: u8 = 30;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[wasm_bindgen]
@@ -18,11 +23,39 @@ pub struct TickResult {
     kind: TickType,
     piece: PieceType,
     lines: u8,
+    placed: bool,
+    mini: bool,
 }
 
 impl TickResult {
-    pub(crate) fn new(kind: TickType, piece: PieceType, lines: u8) -> Self {
-        Self { kind, piece, lines }
+    // A piece locked into the board, clearing the given lines
+    pub(crate) fn locked(piece: PieceType, lines: u8) -> Self {
+        Self {
+            kind: if lines > 0 {
+                TickType::Clear
+            } else {
+                TickType::None
+            },
+            piece,
+            lines,
+            placed: true,
+            mini: false,
+        }
+    }
+
+    pub(crate) fn game_over(self) -> Self {
+        Self {
+            kind: TickType::GameOver,
+            ..self
+        }
+    }
+
+    // Takes back a game over, for modes that cannot be lost
+    pub(crate) fn resume(self) -> Self {
+        Self {
+            kind: TickType::None,
+            ..self
+        }
     }
 }
 
@@ -39,6 +72,16 @@ impl TickResult {
     pub fn lines(&self) -> u8 {
         self.lines
     }
+
+    // Whether this tick locked a piece into the board
+    pub fn placed(&self) -> bool {
+        self.placed
+    }
+
+    // A T-spin without both corners in front of the T filled
+    pub fn mini(&self) -> bool {
+        self.mini
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -61,6 +104,20 @@ pub struct Board {
     may_hold: bool,
     position: Point,
     last_input_rot: bool,
+    stats: PieceStats,
+    gravity: u128,
+    instant_gravity: bool,
+    lock_samples: u8,
+    entry_delay: u128,
+    clear_delay: u128,
+    frozen_until: u128,
+    garbage: [bool; BOARD_HEIGHT],
+    presses: u64,
+    placement: Option<Placement>,
+    spawn: Point,
+    kicks: Kicks,
+    rotate_180: bool,
+    hold_allowed: bool,
 }
 
 impl Board {
@@ -88,6 +145,119 @@ impl Board {
         self.position = position;
     }
 
+    // Milliseconds between each row the piece falls on its own
+    pub(crate) fn set_gravity(&mut self, gravity: u128) {
+        self.gravity = gravity.max(1);
+    }
+
+    // Drops the piece as far as it goes every tick, 20G
+    pub(crate) fn set_instant_gravity(&mut self, instant: bool) {
+        self.instant_gravity = instant;
+    }
+
+    // Lock delay in samples of 50ms the piece has to rest on the stack for
+    pub(crate) fn set_lock_samples(&mut self, samples: u8) {
+        self.lock_samples = samples.max(1);
+    }
+
+    // Milliseconds the board waits before the next piece may move, after a lock and a clear
+    pub(crate) fn set_delays(&mut self, entry: u128, clear: u128) {
+        self.entry_delay = entry;
+        self.clear_delay = clear;
+    }
+
+    // Everything but the scoring, which the game keeps
+    pub(crate) fn set_rules(&mut self, rules: &Ruleset) {
+        if self.position == self.spawn {
+            self.position = Point::new(rules.spawn_column(), self.spawn.y());
+        }
+
+        self.spawn = Point::new(rules.spawn_column(), self.spawn.y());
+        self.kicks = rules.kicks();
+        self.rotate_180 = rules.rotate_180();
+        self.hold_allowed = rules.hold();
+
+        self.set_gravity(rules.gravity() as u128);
+        self.set_lock_samples((rules.lock_delay() / 50).min(u8::MAX as u64) as u8);
+        self.set_delays(rules.entry_delay() as u128, rules.clear_delay() as u128);
+    }
+
+    // Pushes the stack up by one garbage row, returns false if blocks were pushed out the top
+    pub(crate) fn add_garbage(&mut self, hole: usize) -> bool {
+        let topped_out = self.board[0].iter().any(|tile| *tile != Colour::None);
+
+        for y in 0..(BOARD_HEIGHT - 1) {
+            self.board[y] = self.board[y + 1];
+            self.garbage[y] = self.garbage[y + 1];
+        }
+
+        self.board[BOARD_HEIGHT - 1] = [Colour::Grey; BOARD_WIDTH];
+        self.board[BOARD_HEIGHT - 1][hole] = Colour::None;
+        self.garbage[BOARD_HEIGHT - 1] = true;
+
+        while !self.legal_position(self.piece, self.position) && self.position.y() > 0 {
+            self.position = self.position - Point::new(0, 1);
+        }
+
+        !topped_out
+    }
+
+    // Sandbox edits change the board in place, so the stats, garbage rows and hold state carry on
+    pub(crate) fn set_piece(&mut self, piece: Piece) {
+        self.stats.record(piece.kind());
+        self.piece = piece;
+        self.position = self.spawn;
+        self.contact = 0;
+        self.presses = 0;
+    }
+
+    // Swaps the active and the held piece, whether or not holding is allowed right now
+    pub(crate) fn swap_hold(&mut self) {
+        let next = match self.held {
+            Some(held) => Piece::new(held.kind()),
+            None => self.draw(),
+        };
+
+        self.held = Some(Piece::new(self.piece.kind()));
+        self.piece = next;
+        self.position = self.spawn;
+        self.contact = 0;
+        self.presses = 0;
+    }
+
+    pub(crate) fn set_cell(&mut self, x: usize, y: usize, colour: Colour) {
+        self.board[y][x] = colour;
+    }
+
+    // The piece locked most recently and the presses it took
+    pub fn placement(&self) -> Option<Placement> {
+        self.placement
+    }
+
+    // Where a single press takes a piece, used to search for the fewest presses
+    pub(crate) fn after_press(
+        &self,
+        piece: Piece,
+        position: Point,
+        input: Input,
+    ) -> (Piece, Point) {
+        let mut board = self.clone();
+        board.piece = piece;
+        board.position = position;
+
+        board.move_piece(input.direction);
+        board.rotate_piece(input.rotation);
+
+        (board.piece, board.position)
+    }
+
+    fn draw(&mut self) -> Piece {
+        let kind = self.bag.next();
+        self.stats.record(kind);
+
+        Piece::new(kind)
+    }
+
     fn legal_position(&self, piece: Piece, position: Point) -> bool {
         let blocks = piece.blocks();
 
@@ -139,9 +309,18 @@ impl Board {
     fn rotate_180(&mut self) {
         use crate::kicks::KICKS_180;
 
+        if !self.rotate_180 {
+            return;
+        }
+
         let piece = self.piece.rotate(2);
+        let kicks: &[Point] = match self.kicks {
+            Kicks::Srs => &KICKS_180[self.piece.rotation() as usize],
+            Kicks::Basic => &BASIC_KICKS,
+            Kicks::None => &NO_KICKS,
+        };
 
-        for kick in KICKS_180[self.piece.rotation() as usize] {
+        for kick in kicks.iter().copied() {
             let position = self.position + kick;
 
             if self.legal_position(piece, position) {
@@ -162,8 +341,10 @@ impl Board {
         };
 
         let piece = self.piece.rotate(rotation);
-        let mut process_kick = |table: [[[Point; 5]; 2]; 4]| {
-            for kick in table[self.piece.rotation() as usize][delta] {
+        let from = self.piece.rotation() as usize;
+        let system = self.kicks;
+        let mut process_kick = |kicks: &[Point]| {
+            for kick in kicks.iter().copied() {
                 let position = self.position + kick;
 
                 if self.legal_position(piece, position) {
@@ -175,10 +356,12 @@ impl Board {
             }
         };
 
-        match piece.kind() {
-            PieceType::O => {}
-            PieceType::I => process_kick(I_KICKS),
-            _ => process_kick(KICKS),
+        match (piece.kind(), system) {
+            (PieceType::O, _) => {}
+            (PieceType::I, Kicks::Srs) => process_kick(&I_KICKS[from][delta]),
+            (_, Kicks::Srs) => process_kick(&KICKS[from][delta]),
+            (_, Kicks::Basic) => process_kick(&BASIC_KICKS),
+            (_, Kicks::None) => process_kick(&NO_KICKS),
         }
     }
 
@@ -201,6 +384,41 @@ impl Board {
         }
     }
 
+    // Whether both corners on the side the T points to are blocked
+    fn t_front_blocked(&self) -> bool {
+        const SIDES: [Point; 4] = [
+            Point::constant(0, -1),
+            Point::constant(1, 0),
+            Point::constant(0, 1),
+            Point::constant(-1, 0),
+        ];
+
+        let blocks = self.piece.blocks();
+        let center = Point::new(1, 2);
+        let filled = |point: Point| {
+            let (x, y) = (point.x() as usize, point.y() as usize);
+            x < 4 && y < 4 && blocks[y][x] != Colour::None
+        };
+
+        // The T points to the one side of its centre without a block opposite it
+        let front = match SIDES
+            .into_iter()
+            .find(|side| filled(center + *side) && !filled(center - *side))
+        {
+            Some(front) => front,
+            None => return false,
+        };
+
+        let side = Point::new(front.y(), front.x());
+
+        [front + side, front - side].into_iter().all(|offset| {
+            let corner = self.position + center + offset;
+            let (x, y) = (corner.x() as usize, corner.y() as usize);
+
+            x >= BOARD_WIDTH || y >= BOARD_HEIGHT || self.board[y][x] != Colour::None
+        })
+    }
+
     fn next_piece(&mut self) -> TickResult {
         let spin = if self.last_input_rot {
             match self.piece.kind() {
@@ -260,7 +478,14 @@ impl Board {
             false
         };
 
+        let mini = spin && self.piece.kind() == PieceType::T && !self.t_front_blocked();
+
         let piece = self.piece;
+        let dropped = (self.spawn.y()..self.position.y())
+            .all(|y| self.legal_position(piece, Point::new(self.position.x(), y)));
+
+        self.placement = Some(Placement::new(piece, self.position, self.presses, dropped));
+        self.presses = 0;
         let blocks = piece.blocks();
 
         for y in 0..4 {
@@ -275,9 +500,10 @@ impl Board {
             }
         }
 
-        self.piece = Piece::new(self.bag.next());
+        self.piece = self.draw();
+        self.contact = 0;
         self.may_hold = true;
-        self.position = Self::START_POSITION;
+        self.position = self.spawn;
 
         if self.legal_position(self.piece, self.position) {
             let mut cleared_indexes: Vec<usize> = Vec::with_capacity(4);
@@ -298,9 +524,11 @@ impl Board {
             while let Some(i) = cleared_indexes.pop() {
                 for y in (1..=i).rev() {
                     self.board[y] = self.board[y - 1];
+                    self.garbage[y] = self.garbage[y - 1];
                 }
 
                 self.board[0] = [Colour::None; BOARD_WIDTH];
+                self.garbage[0] = false;
             }
 
             if spin {
@@ -309,23 +537,15 @@ impl Board {
                         kind: TickType::Spin,
                         piece: piece.kind(),
                         lines: cleared,
+                        placed: true,
+                        mini,
                     },
                     _ => unreachable!("It should be impossible to clear outside the range of 0-4"),
                 };
             }
 
             match cleared {
-                0 => TickResult {
-                    kind: TickType::None,
-                    piece: piece.kind(),
-                    lines: 0,
-                },
-
-                1 | 2 | 3 | 4 => TickResult {
-                    kind: TickType::Clear,
-                    piece: piece.kind(),
-                    lines: cleared,
-                },
+                0..=4 => TickResult::locked(piece.kind(), cleared),
                 _ => unreachable!("It should be impossible to clear outside the range of 0-4"),
             }
         } else {
@@ -333,6 +553,8 @@ impl Board {
                 kind: TickType::GameOver,
                 piece: piece.kind(),
                 lines: 0,
+                placed: true,
+                mini: false,
             }
         }
     }
@@ -346,17 +568,18 @@ impl Board {
     }
 
     fn hold(&mut self) {
-        if self.may_hold {
+        if self.may_hold && self.hold_allowed {
             if let Some(held) = self.held {
                 self.held = Some(Piece::new(self.piece.kind()));
                 self.piece = Piece::new(held.kind());
             } else {
                 self.held = Some(Piece::new(self.piece.kind()));
-                self.piece = Piece::new(self.bag.next());
+                self.piece = self.draw();
             }
 
-            self.position = Self::START_POSITION;
-            self.may_hold = false
+            self.position = self.spawn;
+            self.may_hold = false;
+            self.presses = 0;
         }
     }
 
@@ -366,9 +589,14 @@ impl Board {
                 kind: TickType::GameOver,
                 piece: self.piece.kind(),
                 lines: 0,
+                placed: false,
+                mini: false,
             };
         }
 
+        self.presses += (input.direction != InputDirection::None) as u64
+            + (input.rotation != InputRotation::None) as u64;
+
         self.move_piece(input.direction);
         self.rotate_piece(input.rotation);
 
@@ -388,12 +616,35 @@ impl Board {
             kind: TickType::None,
             piece: self.piece.kind(),
             lines: 0,
+            placed: false,
+            mini: false,
         }
     }
 
     #[inline(always)]
-    fn tick_inner(&mut self, input: Input, tick: u128) -> TickResult {
-        if tick % 500 == 0 {
+    pub(crate) fn tick_inner(&mut self, input: Input, tick: u128) -> TickResult {
+        if tick < self.frozen_until && !input.quit {
+            return TickResult {
+                kind: TickType::None,
+                piece: self.piece.kind(),
+                lines: 0,
+                placed: false,
+                mini: false,
+            };
+        }
+
+        let res = self.step(input, tick);
+
+        if res.placed {
+            let clear_delay = if res.lines > 0 { self.clear_delay } else { 0 };
+            self.frozen_until = tick + self.entry_delay + clear_delay;
+        }
+
+        res
+    }
+
+    fn step(&mut self, input: Input, tick: u128) -> TickResult {
+        if tick % self.gravity == 0 {
             self.soft_drop();
         }
 
@@ -404,12 +655,20 @@ impl Board {
                 self.contact = 0;
             }
 
-            if self.contact == 30 {
+            if self.contact >= self.lock_samples {
                 return self.next_piece();
             }
         }
 
-        self.input(input)
+        let res = self.input(input);
+
+        if self.instant_gravity && !res.placed {
+            while self.test_soft_drop() {
+                self.soft_drop();
+            }
+        }
+
+        res
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -419,6 +678,8 @@ impl Board {
 
     pub fn from_strs(rows: &[&str], mut bag: Bag) -> Self {
         let piece = Piece::new(bag.next());
+        let mut stats = PieceStats::new();
+        stats.record(piece.kind());
 
         let mut board = [[Colour::None; BOARD_WIDTH]; BOARD_HEIGHT];
 
@@ -453,6 +714,20 @@ impl Board {
             may_hold: true,
             position: Self::START_POSITION,
             last_input_rot: false,
+            stats,
+            gravity: GRAVITY,
+            instant_gravity: false,
+            lock_samples: LOCK_SAMPLES,
+            entry_delay: 0,
+            clear_delay: 0,
+            frozen_until: 0,
+            garbage: [false; BOARD_HEIGHT],
+            presses: 0,
+            placement: None,
+            spawn: Self::START_POSITION,
+            kicks: Kicks::Srs,
+            rotate_180: true,
+            hold_allowed: true,
         }
     }
 
@@ -462,6 +737,9 @@ impl Board {
         piece: Piece,
         held: Option<Piece>,
     ) -> Self {
+        let mut stats = PieceStats::new();
+        stats.record(piece.kind());
+
         Self {
             bag,
             held,
@@ -471,6 +749,20 @@ impl Board {
             may_hold: true,
             position: Self::START_POSITION,
             last_input_rot: false,
+            stats,
+            gravity: GRAVITY,
+            instant_gravity: false,
+            lock_samples: LOCK_SAMPLES,
+            entry_delay: 0,
+            clear_delay: 0,
+            frozen_until: 0,
+            garbage: [false; BOARD_HEIGHT],
+            presses: 0,
+            placement: None,
+            spawn: Self::START_POSITION,
+            kicks: Kicks::Srs,
+            rotate_180: true,
+            hold_allowed: true,
         }
     }
 }
@@ -479,6 +771,8 @@ impl Board {
 impl Board {
     pub fn new(mut bag: Bag) -> Self {
         let piece = Piece::new(bag.next());
+        let mut stats = PieceStats::new();
+        stats.record(piece.kind());
 
         Self {
             bag,
@@ -489,6 +783,20 @@ impl Board {
             may_hold: true,
             position: Self::START_POSITION,
             last_input_rot: false,
+            stats,
+            gravity: GRAVITY,
+            instant_gravity: false,
+            lock_samples: LOCK_SAMPLES,
+            entry_delay: 0,
+            clear_delay: 0,
+            frozen_until: 0,
+            garbage: [false; BOARD_HEIGHT],
+            presses: 0,
+            placement: None,
+            spawn: Self::START_POSITION,
+            kicks: Kicks::Srs,
+            rotate_180: true,
+            hold_allowed: true,
         }
     }
 
@@ -520,6 +828,19 @@ impl Board {
         Piece::new(self.bag.peek(i))
     }
 
+    pub fn stats(&self) -> PieceStats {
+        self.stats
+    }
+
+    // Rows of garbage that have not been cleared yet
+    pub fn garbage_rows(&self) -> usize {
+        self.garbage.iter().filter(|row| **row).count()
+    }
+
+    pub fn is_garbage(&self, y: usize) -> bool {
+        self.garbage[y]
+    }
+
     #[cfg(target_arch = "wasm32")]
     pub fn tick(&mut self, input: Input, tick: u64) -> TickResult {
         self.tick_inner(input, tick as u128)
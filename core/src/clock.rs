@@ -0,0 +1,52 @@
+/*
+ * The game's own clock, read against milliseconds of real time. It stands
+ * still while paused, so the ticks it hands out, and with them a replay's
+ * duration, only count the time spent playing
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Clock {
+    tick: u128,
+    offset: u128,
+    paused: Option<u128>,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Ticks handed out so far, the duration of the game
+    pub fn tick(&self) -> u128 {
+        self.tick
+    }
+
+    // Game time at the given real time
+    pub fn time(&self, now: u128) -> u128 {
+        self.paused.unwrap_or(now) - self.offset
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    pub fn pause(&mut self, now: u128) {
+        self.paused.get_or_insert(now);
+    }
+
+    pub fn resume(&mut self, now: u128) {
+        if let Some(paused) = self.paused.take() {
+            self.offset += now - paused;
+        }
+    }
+
+    // The next tick to play when the game is behind the real time, every millisecond gets one
+    pub fn next(&mut self, now: u128) -> Option<u128> {
+        if self.tick >= self.time(now) {
+            return None;
+        }
+
+        self.tick += 1;
+
+        Some(self.tick)
+    }
+}
@@ -0,0 +1,505 @@
+use std::{collections::VecDeque, fmt::Display, str::FromStr};
+
+use crate::{
+    json::{quote, Json},
+    replay::VERSION,
+    Edit, Frame, GameMode, GameType, Grade, Input, InputDirection, InputRotation, Metadata,
+    PieceType, Replay, Ruleset, Score,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportError {
+    // The file does not parse past this line
+    Syntax(usize),
+    MissingField(&'static str),
+    InvalidField(&'static str),
+    InvalidInput(String),
+}
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::Syntax(line) => write!(f, "Unable to parse line {}", line),
+            ImportError::MissingField(field) => write!(f, "Missing field \"{}\"", field),
+            ImportError::InvalidField(field) => write!(f, "Invalid value for \"{}\"", field),
+            ImportError::InvalidInput(input) => write!(f, "Unknown input \"{}\"", input),
+        }
+    }
+}
+
+const MODES: [(GameMode, &str); 9] = [
+    (GameMode::Marathon, "marathon"),
+    (GameMode::Lines, "lines"),
+    (GameMode::Time, "time"),
+    (GameMode::Dig, "dig"),
+    (GameMode::Survival, "survival"),
+    (GameMode::Master, "master"),
+    (GameMode::Puzzle, "puzzle"),
+    (GameMode::Sandbox, "sandbox"),
+    (GameMode::Versus, "versus"),
+];
+
+const FLAGS: [&str; 4] = ["hold", "quit", "hard_drop", "soft_drop"];
+
+const ROTATIONS: [(InputRotation, &str); 3] = [
+    (InputRotation::Quarter, "rotate_90"),
+    (InputRotation::TwoQuarter, "rotate_180"),
+    (InputRotation::ThreeQuarter, "rotate_270"),
+];
+
+const DIRECTIONS: [(InputDirection, &str); 4] = [
+    (InputDirection::Left, "left"),
+    (InputDirection::Right, "right"),
+    (InputDirection::SnapLeft, "snap_left"),
+    (InputDirection::SnapRight, "snap_right"),
+];
+
+// Undoes the escaping of metadata values in text
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            }
+            (c, _) => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
+
+/*
+ * An input is written as the names of what is pressed, edits as
+ * piece:T, swap_hold, toggle:3:39 or garbage
+ */
+fn input_names(input: &Input) -> Vec<String> {
+    let flags = [input.hold, input.quit, input.hard_drop, input.soft_drop];
+    let mut names: Vec<String> = FLAGS
+        .iter()
+        .zip(flags)
+        .filter(|(_, pressed)| *pressed)
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    names.extend(
+        ROTATIONS
+            .iter()
+            .filter(|(rotation, _)| *rotation == input.rotation)
+            .map(|(_, name)| name.to_string()),
+    );
+
+    names.extend(
+        DIRECTIONS
+            .iter()
+            .filter(|(direction, _)| *direction == input.direction)
+            .map(|(_, name)| name.to_string()),
+    );
+
+    names.extend(input.edit.map(|edit| match edit {
+        Edit::Piece(kind) => format!("piece:{:?}", kind),
+        Edit::SwapHold => String::from("swap_hold"),
+        Edit::Toggle(x, y) => format!("toggle:{}:{}", x, y),
+        Edit::Garbage => String::from("garbage"),
+    }));
+
+    names
+}
+
+fn press(input: &mut Input, name: &str) -> Result<(), ImportError> {
+    let invalid = || ImportError::InvalidInput(name.to_string());
+    let number = |value: Option<&str>| {
+        value
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(invalid)
+    };
+
+    if let Some(rotation) = ROTATIONS.iter().find(|(_, n)| *n == name) {
+        input.rotation = rotation.0;
+    } else if let Some(direction) = DIRECTIONS.iter().find(|(_, n)| *n == name) {
+        input.direction = direction.0;
+    } else {
+        let mut parts = name.split(':');
+
+        match parts.next().unwrap_or_default() {
+            "hold" => input.hold = true,
+            "quit" => input.quit = true,
+            "hard_drop" => input.hard_drop = true,
+            "soft_drop" => input.soft_drop = true,
+            "swap_hold" => input.edit = Some(Edit::SwapHold),
+            "garbage" => input.edit = Some(Edit::Garbage),
+            "toggle" => {
+                input.edit = Some(Edit::Toggle(number(parts.next())?, number(parts.next())?))
+            }
+            "piece" => {
+                let kind = parts.next().ok_or_else(invalid)?;
+                let kind = PieceType::ALL
+                    .into_iter()
+                    .find(|piece| format!("{:?}", piece).eq_ignore_ascii_case(kind))
+                    .ok_or_else(invalid)?;
+
+                input.edit = Some(Edit::Piece(kind));
+            }
+            _ => return Err(invalid()),
+        }
+
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+    }
+
+    Ok(())
+}
+
+impl Replay {
+    // Header fields by name, with whether JSON should write them as strings
+    fn fields(&self) -> Vec<(&'static str, String, bool)> {
+        let mode = MODES.iter().find(|(mode, _)| *mode == self.kind.mode());
+
+        vec![
+            ("username", self.username.clone(), true),
+            ("version", self.version.to_string(), false),
+            ("mode", mode.unwrap().1.to_string(), true),
+            ("count", self.kind.lines().to_string(), false),
+            ("start_level", self.kind.level().to_string(), false),
+            ("score", self.score.score().to_string(), false),
+            ("lines", self.score.lines().to_string(), false),
+            ("level", self.score.level().to_string(), false),
+            ("grade", self.grade.to_string(), true),
+            ("duration", self.duration.to_string(), false),
+            // Too large for the numbers of most JSON readers
+            ("seed", self.seed.to_string(), true),
+            ("time_stamp", self.time_stamp.to_string(), false),
+            ("rules", format!("{:016x}", self.rules), true),
+        ]
+    }
+
+    fn frame_lists(&self) -> Vec<(&'static str, &VecDeque<Frame>)> {
+        if self.kind.mode() == GameMode::Versus {
+            vec![("frames", &self.frames), ("opponent", &self.opponent)]
+        } else {
+            vec![("frames", &self.frames)]
+        }
+    }
+
+    // Every field and frame as JSON, frames at their time since the start with the inputs by name
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\n");
+
+        for (name, value, string) in self.fields() {
+            let value = if string { quote(&value) } else { value };
+            json.push_str(&format!("  {}: {},\n", quote(name), value));
+        }
+
+        let metadata: Vec<String> = self
+            .metadata
+            .iter()
+            .map(|(key, value)| format!("    {}: {}", quote(key.name()), quote(value)))
+            .collect();
+
+        if metadata.is_empty() {
+            json.push_str("  \"metadata\": {},\n");
+        } else {
+            json.push_str(&format!(
+                "  \"metadata\": {{\n{}\n  }},\n",
+                metadata.join(",\n")
+            ));
+        }
+
+        let lists: Vec<String> = self
+            .frame_lists()
+            .into_iter()
+            .map(|(name, frames)| {
+                let frames: Vec<String> = frames
+                    .iter()
+                    .map(|frame| {
+                        let names: Vec<String> = input_names(&frame.input)
+                            .iter()
+                            .map(|name| quote(name))
+                            .collect();
+
+                        format!(
+                            "    {{ \"time\": {}, \"input\": [{}] }}",
+                            frame.time,
+                            names.join(", ")
+                        )
+                    })
+                    .collect();
+
+                format!("  {}: [\n{}\n  ]", quote(name), frames.join(",\n"))
+            })
+            .collect();
+
+        json.push_str(&lists.join(",\n"));
+        json.push_str("\n}\n");
+
+        json
+    }
+
+    /*
+     * Fields as name = value lines, metadata as metadata.name = value with
+     * line breaks and backslashes escaped, then a line with [frames]
+     * followed by a line for each frame, its time and the inputs by name
+     *
+     * metadata.notes = first line\nsecond line
+     *
+     * [frames]
+     * 0
+     * 250 left hard_drop
+     */
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for (name, value, _) in self.fields() {
+            text.push_str(&format!("{} = {}\n", name, value));
+        }
+
+        for (key, value) in &self.metadata {
+            let value = value.replace('\\', "\\\\").replace('\n', "\\n");
+            text.push_str(&format!("metadata.{} = {}\n", key.name(), value));
+        }
+
+        for (name, frames) in self.frame_lists() {
+            text.push_str(&format!("\n[{}]\n", name));
+
+            for frame in frames {
+                let mut line = frame.time.to_string();
+
+                for name in input_names(&frame.input) {
+                    line.push(' ');
+                    line.push_str(&name);
+                }
+
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+
+        text
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, ImportError> {
+        let json = Json::parse(s).map_err(ImportError::Syntax)?;
+
+        let field = |name: &str| match json.get(name) {
+            Some(Json::String(value) | Json::Number(value)) => Some(value.clone()),
+            _ => None,
+        };
+
+        let frames = |name: &'static str| -> Result<Vec<Frame>, ImportError> {
+            let list = match json.get(name) {
+                Some(Json::Array(list)) => list,
+                Some(_) => return Err(ImportError::InvalidField(name)),
+                None => return Ok(Vec::new()),
+            };
+
+            list.iter()
+                .map(|frame| {
+                    let time = match frame.get("time") {
+                        Some(Json::Number(time)) => time.parse(),
+                        _ => return Err(ImportError::InvalidField(name)),
+                    };
+
+                    let mut input = Input::default();
+
+                    match frame.get("input") {
+                        Some(Json::Array(names)) => {
+                            for name in names {
+                                match name {
+                                    Json::String(name) => press(&mut input, name)?,
+                                    _ => return Err(ImportError::InvalidField("input")),
+                                }
+                            }
+                        }
+                        None => {}
+                        Some(_) => return Err(ImportError::InvalidField("input")),
+                    }
+
+                    Ok(Frame {
+                        time: time.map_err(|_| ImportError::InvalidField(name))?,
+                        input,
+                    })
+                })
+                .collect()
+        };
+
+        let metadata = match json.get("metadata") {
+            Some(Json::Object(entries)) => entries
+                .iter()
+                .map(|(name, value)| match value {
+                    Json::String(value) => Ok((name.as_str(), value.clone())),
+                    _ => Err(ImportError::InvalidField("metadata")),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => return Err(ImportError::InvalidField("metadata")),
+            None => Vec::new(),
+        };
+
+        let mut replay = Self::build(field, frames("frames")?, frames("opponent")?)?;
+        replay.import_metadata(metadata);
+
+        Ok(replay)
+    }
+
+    pub fn from_text(s: &str) -> Result<Self, ImportError> {
+        let mut fields = Vec::new();
+        let mut lists: [Vec<Frame>; 2] = Default::default();
+        let mut list = None;
+
+        for (i, line) in s.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match (line, list) {
+                ("[frames]", _) => list = Some(0),
+                ("[opponent]", _) => list = Some(1),
+                (line, None) => match line.split_once('=') {
+                    Some((name, value)) => {
+                        fields.push((name.trim().to_string(), value.trim().to_string()))
+                    }
+                    None => return Err(ImportError::Syntax(i)),
+                },
+                (line, Some(list)) => {
+                    let mut words = line.split_whitespace();
+                    let time = match words.next().map(str::parse) {
+                        Some(Ok(time)) => time,
+                        _ => return Err(ImportError::Syntax(i)),
+                    };
+
+                    let mut input = Input::default();
+
+                    for name in words {
+                        press(&mut input, name)?;
+                    }
+
+                    lists[list].push(Frame { time, input });
+                }
+            }
+        }
+
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.clone())
+        };
+
+        let metadata = fields
+            .iter()
+            .filter_map(|(name, value)| Some((name.strip_prefix("metadata.")?, unescape(value))))
+            .collect();
+
+        let [frames, opponent] = lists;
+        let mut replay = Self::build(field, frames, opponent)?;
+        replay.import_metadata(metadata);
+
+        Ok(replay)
+    }
+
+    // Entries are matched by name, ones this version does not know are left out like unknown tags
+    fn import_metadata(&mut self, entries: Vec<(&str, String)>) {
+        for (name, value) in entries {
+            if let Some(key) = Metadata::ALL.into_iter().find(|key| key.name() == name) {
+                self.set_metadata(key, value);
+            }
+        }
+    }
+
+    // Anything but the username, mode and seed may be left out of an imported replay
+    fn build<F: Fn(&str) -> Option<String>>(
+        field: F,
+        frames: Vec<Frame>,
+        opponent: Vec<Frame>,
+    ) -> Result<Self, ImportError> {
+        fn parse<T: FromStr>(value: Option<String>, name: &'static str) -> Result<T, ImportError> {
+            let value = value.ok_or(ImportError::MissingField(name))?;
+            value.parse().map_err(|_| ImportError::InvalidField(name))
+        }
+
+        let number = |name: &'static str, default: u64| match field(name) {
+            Some(value) => parse(Some(value), name),
+            None => Ok(default),
+        };
+
+        let username = field("username").ok_or(ImportError::MissingField("username"))?;
+
+        // Kept as exported, so an older game does not come back as a verifiable one
+        let version = number("version", VERSION as u64)?;
+        let version = match u8::try_from(version) {
+            Ok(version @ 1..=VERSION) => version,
+            _ => return Err(ImportError::InvalidField("version")),
+        };
+
+        let mode = field("mode").ok_or(ImportError::MissingField("mode"))?;
+        let mode = match MODES
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(&mode))
+        {
+            Some((mode, _)) => *mode,
+            None => return Err(ImportError::InvalidField("mode")),
+        };
+
+        let start_level = number("start_level", 1)?;
+        let kind = GameType::new(mode, number("count", 0)?).with_level(start_level);
+
+        let score = Score {
+            score: number("score", 0)?,
+            lines: number("lines", 0)?,
+            level: number("level", start_level)?,
+        };
+
+        let grade = match field("grade") {
+            Some(grade) => (0..=Grade::GrandMaster as u8)
+                .filter_map(|grade| Grade::new(grade).ok())
+                .find(|known| known.to_string().eq_ignore_ascii_case(&grade))
+                .ok_or(ImportError::InvalidField("grade"))?,
+            None => Grade::Nine,
+        };
+
+        let rules = match field("rules") {
+            Some(rules) => {
+                u64::from_str_radix(&rules, 16).map_err(|_| ImportError::InvalidField("rules"))?
+            }
+            None => Ruleset::guideline().hash(),
+        };
+
+        let time_stamp = match field("time_stamp") {
+            Some(value) => parse(Some(value), "time_stamp")?,
+            None => 0,
+        };
+
+        // Times are from the start of the game, so they may never go back
+        for (name, frames) in [("frames", &frames), ("opponent", &opponent)] {
+            if frames.windows(2).any(|pair| pair[1].time < pair[0].time) {
+                return Err(ImportError::InvalidField(name));
+            }
+        }
+
+        Ok(Self {
+            username,
+            version,
+            score,
+            grade,
+            duration: number("duration", 0)?,
+            time_stamp,
+            seed: parse(field("seed"), "seed")?,
+            kind,
+            rules,
+            metadata: Vec::new(),
+            frames: frames.into(),
+            opponent: if mode == GameMode::Versus {
+                opponent.into()
+            } else {
+                VecDeque::new()
+            },
+        })
+    }
+}
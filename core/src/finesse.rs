@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+
+use crate::{
+    input::{Input, InputDirection, InputRotation},
+    piece::{Piece, PieceType},
+    point::Point,
+    Bag, Board, Colour, Ruleset,
+};
+
+// Every single press that moves a piece, hard drops are not counted
+const PRESSES: [Input; 7] = {
+    const fn direction(direction: InputDirection) -> Input {
+        Input {
+            hold: false,
+            quit: false,
+            hard_drop: false,
+            soft_drop: false,
+            rotation: InputRotation::None,
+            direction,
+            edit: None,
+        }
+    }
+
+    const fn rotation(rotation: InputRotation) -> Input {
+        Input {
+            rotation,
+            ..direction(InputDirection::None)
+        }
+    }
+
+    [
+        direction(InputDirection::Left),
+        direction(InputDirection::Right),
+        direction(InputDirection::SnapLeft),
+        direction(InputDirection::SnapRight),
+        rotation(InputRotation::Quarter),
+        rotation(InputRotation::ThreeQuarter),
+        rotation(InputRotation::TwoQuarter),
+    ]
+};
+
+// The cells a piece covers on the board, sorted so they can be compared
+fn cells(piece: Piece, position: Point) -> [(i8, i8); 4] {
+    let blocks = piece.blocks();
+    let mut cells = [(0, 0); 4];
+    let mut i = 0;
+
+    for y in 0..4 {
+        for x in 0..4 {
+            if blocks[y][x] != Colour::None {
+                cells[i] = (x as i8 + position.x(), y as i8 + position.y());
+                i += 1;
+            }
+        }
+    }
+
+    cells.sort_unstable();
+    cells
+}
+
+// The cells a piece covers with rows counted from its lowest block
+fn shape(piece: Piece, position: Point) -> [(i8, i8); 4] {
+    let mut cells = cells(piece, position);
+    let bottom = cells.iter().map(|(_, y)| *y).max().unwrap();
+
+    for cell in cells.iter_mut() {
+        cell.1 -= bottom;
+    }
+
+    cells
+}
+
+// A locked piece and the presses spent on it since it entered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placement {
+    piece: Piece,
+    position: Point,
+    presses: u64,
+    dropped: bool,
+}
+
+impl Placement {
+    pub(crate) fn new(piece: Piece, position: Point, presses: u64, dropped: bool) -> Self {
+        Self {
+            piece,
+            position,
+            presses,
+            dropped,
+        }
+    }
+
+    pub fn piece(&self) -> Piece {
+        self.piece
+    }
+
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    pub fn presses(&self) -> u64 {
+        self.presses
+    }
+
+    // Whether the piece could have been dropped straight down into place, tucks and spins could not
+    pub fn dropped(&self) -> bool {
+        self.dropped
+    }
+
+    // Whether the piece covers the same cells, different rotations of I, S, Z and O can
+    pub fn covers(&self, piece: Piece, position: Point) -> bool {
+        cells(self.piece, self.position) == cells(piece, position)
+    }
+}
+
+// How one piece compared to the fewest presses that place it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FinesseResult {
+    piece: PieceType,
+    presses: u64,
+    optimal: u64,
+}
+
+impl FinesseResult {
+    pub fn piece(&self) -> PieceType {
+        self.piece
+    }
+
+    pub fn presses(&self) -> u64 {
+        self.presses
+    }
+
+    pub fn optimal(&self) -> u64 {
+        self.optimal
+    }
+
+    // Presses spent beyond the fewest possible
+    pub fn faults(&self) -> u64 {
+        self.presses.saturating_sub(self.optimal)
+    }
+}
+
+// Finesse over a whole game, only pieces dropped straight into place are judged
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Finesse {
+    last: Option<FinesseResult>,
+    judged: u64,
+    faulty: u64,
+    faults: u64,
+}
+
+impl Finesse {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        placement: Placement,
+        rules: &Ruleset,
+    ) -> Option<FinesseResult> {
+        self.last = if placement.dropped() {
+            let res = FinesseResult {
+                piece: placement.piece().kind(),
+                presses: placement.presses(),
+                optimal: Self::optimal(placement.piece(), placement.position(), rules),
+            };
+
+            self.judged += 1;
+            self.faulty += (res.faults() > 0) as u64;
+            self.faults += res.faults();
+
+            Some(res)
+        } else {
+            None
+        };
+
+        self.last
+    }
+
+    // The most recent piece, None if it was not judged
+    pub fn last(&self) -> Option<FinesseResult> {
+        self.last
+    }
+
+    pub fn judged(&self) -> u64 {
+        self.judged
+    }
+
+    // Pieces placed with at least one fault
+    pub fn faulty(&self) -> u64 {
+        self.faulty
+    }
+
+    pub fn faults(&self) -> u64 {
+        self.faults
+    }
+
+    // Fewest presses that line a piece up above where it lies
+    pub fn optimal(piece: Piece, position: Point, rules: &Ruleset) -> u64 {
+        let target = shape(piece, position);
+
+        Self::placements(piece.kind(), rules)
+            .into_iter()
+            .find(|(piece, position, _)| shape(*piece, *position) == target)
+            .map_or(0, |(_, _, presses)| presses)
+    }
+
+    // Every distinct way a piece can land on an empty board with the fewest presses reaching it,
+    // by the spawn, kicks and rotations the rules allow
+    pub fn placements(kind: PieceType, rules: &Ruleset) -> Vec<(Piece, Point, u64)> {
+        let mut board = Board::new(Bag::new(0));
+        board.set_rules(rules);
+
+        let start = (Piece::new(kind), board.position());
+
+        let mut seen = vec![start];
+        let mut queue = VecDeque::from([(start, 0)]);
+        let mut placements: Vec<(Piece, Point, u64)> = Vec::new();
+
+        while let Some(((piece, position), presses)) = queue.pop_front() {
+            let landed = Point::new(position.x(), Board::HEIGHT as i8 - 1 - Self::bottom(piece));
+
+            if placements
+                .iter()
+                .all(|(other, at, _)| shape(*other, *at) != shape(piece, landed))
+            {
+                placements.push((piece, landed, presses));
+            }
+
+            for input in PRESSES {
+                let next = board.after_press(piece, position, input);
+
+                if !seen.contains(&next) {
+                    seen.push(next);
+                    queue.push_back((next, presses + 1));
+                }
+            }
+        }
+
+        placements
+    }
+
+    // Row of the lowest block within the piece
+    fn bottom(piece: Piece) -> i8 {
+        let blocks = piece.blocks();
+
+        (0..4)
+            .rev()
+            .find(|y| blocks[*y].iter().any(|block| *block != Colour::None))
+            .unwrap() as i8
+    }
+}
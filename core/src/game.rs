@@ -1,8 +1,18 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use crate::{point::Point, Bag, Board, Colour, Input, Piece, Score, TickResult, TickType};
+use crate::{
+    attack::{Attack, AttackTable},
+    finesse::Finesse,
+    garbage::Garbage,
+    grade::{Grade, Grading},
+    point::Point,
+    puzzle::{Puzzle, PuzzleState, PuzzleStatus},
+    ruleset::Ruleset,
+    sandbox::Edit,
+    Bag, Board, Colour, Input, Piece, PieceStats, Score, TickResult, TickType,
+};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[wasm_bindgen]
@@ -11,6 +21,12 @@ pub enum GameMode {
     Marathon = 0,
     Lines = 1,
     Time = 2,
+    Dig = 3,
+    Survival = 4,
+    Master = 5,
+    Puzzle = 6,
+    Sandbox = 7,
+    Versus = 8,
 }
 
 impl GameMode {
@@ -19,6 +35,12 @@ impl GameMode {
             0 => Ok(Self::Marathon),
             1 => Ok(Self::Lines),
             2 => Ok(Self::Time),
+            3 => Ok(Self::Dig),
+            4 => Ok(Self::Survival),
+            5 => Ok(Self::Master),
+            6 => Ok(Self::Puzzle),
+            7 => Ok(Self::Sandbox),
+            8 => Ok(Self::Versus),
             _ => Err(()),
         }
     }
@@ -29,35 +51,114 @@ impl GameMode {
 pub struct GameType {
     mode: GameMode,
     count: u64,
+    level: u64,
+}
+
+impl GameType {
+    pub const MARATHON_LINES: u64 = 150;
+    pub const DIG_ROWS: u64 = 10;
+    pub const MASTER_LEVELS: u64 = 999;
+    pub const MAX_START_LEVEL: u64 = 20;
 }
 
 #[wasm_bindgen]
 impl GameType {
     pub fn new(mode: GameMode, count: u64) -> Self {
-        Self { mode, count }
+        Self {
+            mode,
+            count,
+            level: 1,
+        }
     }
 
     pub fn new_marathon() -> Self {
+        Self {
+            mode: GameMode::Marathon,
+            count: Self::MARATHON_LINES,
+            level: 1,
+        }
+    }
+
+    pub fn new_endless() -> Self {
         Self {
             mode: GameMode::Marathon,
             count: 0,
+            level: 1,
         }
     }
 
     pub fn new_lines(lines: u64) -> Self {
         Self {
-            mode: GameMode::Marathon,
+            mode: GameMode::Lines,
             count: lines,
+            level: 1,
         }
     }
 
     pub fn new_timed(seconds: u64) -> Self {
         Self {
-            mode: GameMode::Marathon,
+            mode: GameMode::Time,
             count: seconds,
+            level: 1,
+        }
+    }
+
+    pub fn new_dig(rows: u64) -> Self {
+        Self {
+            mode: GameMode::Dig,
+            count: rows,
+            level: 1,
+        }
+    }
+
+    pub fn new_survival() -> Self {
+        Self {
+            mode: GameMode::Survival,
+            count: 0,
+            level: 1,
+        }
+    }
+
+    pub fn new_master() -> Self {
+        Self {
+            mode: GameMode::Master,
+            count: Self::MASTER_LEVELS,
+            level: 1,
         }
     }
 
+    pub fn new_puzzle() -> Self {
+        Self {
+            mode: GameMode::Puzzle,
+            count: 0,
+            level: 1,
+        }
+    }
+
+    pub fn new_sandbox() -> Self {
+        Self {
+            mode: GameMode::Sandbox,
+            count: 0,
+            level: 1,
+        }
+    }
+
+    pub fn new_versus() -> Self {
+        Self {
+            mode: GameMode::Versus,
+            count: 0,
+            level: 1,
+        }
+    }
+
+    pub fn with_level(&self, level: u64) -> Self {
+        Self {
+            level: level.clamp(1, Self::MAX_START_LEVEL),
+            ..*self
+        }
+    }
+
+    // For marathon this is the line cap, 0 when endless
     pub fn lines(&self) -> u64 {
         self.count
     }
@@ -66,6 +167,21 @@ impl GameType {
         self.count
     }
 
+    // Total garbage rows to clear in dig mode
+    pub fn rows(&self) -> u64 {
+        self.count
+    }
+
+    // Milliseconds garbage waits before rising in versus
+    pub fn garbage_delay(&self) -> u64 {
+        self.count
+    }
+
+    // The level the game starts at
+    pub fn level(&self) -> u64 {
+        self.level
+    }
+
     pub fn mode(&self) -> GameMode {
         self.mode
     }
@@ -74,10 +190,112 @@ impl GameType {
 impl Display for GameType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.mode {
-            GameMode::Marathon => write!(f, "Marathon"),
-            GameMode::Lines => write!(f, "{}L", self.count),
-            GameMode::Time => write!(f, "{}S", self.count),
+            GameMode::Marathon => match self.count {
+                0 => write!(f, "Endless"),
+                Self::MARATHON_LINES => write!(f, "Marathon"),
+                lines => write!(f, "Marathon{}", lines),
+            }?,
+            GameMode::Lines => write!(f, "{}L", self.count)?,
+            GameMode::Time => write!(f, "{}S", self.count)?,
+            GameMode::Dig => match self.count {
+                Self::DIG_ROWS => write!(f, "Dig"),
+                rows => write!(f, "Dig{}", rows),
+            }?,
+            GameMode::Survival => write!(f, "Survival")?,
+            GameMode::Master => write!(f, "Master")?,
+            GameMode::Puzzle => write!(f, "Puzzle")?,
+            GameMode::Sandbox => write!(f, "Sandbox")?,
+            GameMode::Versus => write!(f, "Versus")?,
+        }
+
+        if self.level > 1 {
+            write!(f, " L{}", self.level)?;
         }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GameTypeError {
+    UnknownMode,
+    InvalidCount,
+    InvalidLevel,
+}
+
+impl Display for GameTypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                GameTypeError::UnknownMode =>
+                    "Unknown mode, expected Marathon, Endless, Dig, Survival, Master, Sandbox, <lines>L or <seconds>S",
+                GameTypeError::InvalidCount => "Line and time goals must be a positive number",
+                GameTypeError::InvalidLevel => "Starting level must be written as L1 to L20",
+            }
+        )
+    }
+}
+
+impl FromStr for GameType {
+    type Err = GameTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let mode = words.next().unwrap_or_default();
+
+        let level = match words.next() {
+            Some(level) if level.starts_with(['L', 'l']) => match level[1..].parse::<u64>() {
+                Ok(level) if (1..=Self::MAX_START_LEVEL).contains(&level) => level,
+                _ => return Err(GameTypeError::InvalidLevel),
+            },
+            Some(_) => return Err(GameTypeError::InvalidLevel),
+            None => 1,
+        };
+
+        if words.next().is_some() {
+            return Err(GameTypeError::UnknownMode);
+        }
+
+        let count = |count: &str| match count.parse::<u64>() {
+            Ok(count) if count > 0 => Ok(count),
+            _ => Err(GameTypeError::InvalidCount),
+        };
+
+        let lower = mode.to_ascii_lowercase();
+
+        let kind = if lower == "endless" {
+            Self::new_endless()
+        } else if let Some(lines) = lower.strip_prefix("marathon") {
+            if lines.is_empty() {
+                Self::new_marathon()
+            } else {
+                Self::new(GameMode::Marathon, count(lines)?)
+            }
+        } else if level > 1 {
+            return Err(GameTypeError::InvalidLevel);
+        } else if lower == "master" {
+            Self::new_master()
+        } else if lower == "sandbox" || lower == "zen" {
+            Self::new_sandbox()
+        } else if lower == "survival" {
+            Self::new_survival()
+        } else if let Some(rows) = lower.strip_prefix("dig") {
+            if rows.is_empty() {
+                Self::new_dig(Self::DIG_ROWS)
+            } else {
+                Self::new_dig(count(rows)?)
+            }
+        } else if let Some(lines) = lower.strip_suffix('l') {
+            Self::new_lines(count(lines)?)
+        } else if let Some(seconds) = lower.strip_suffix('s') {
+            Self::new_timed(count(seconds)?)
+        } else {
+            return Err(GameTypeError::UnknownMode);
+        };
+
+        Ok(kind.with_level(level))
     }
 }
 
@@ -87,9 +305,69 @@ pub struct Game {
     board: Board,
     score: Score,
     kind: GameType,
+    garbage: Garbage,
+    next_garbage: u128,
+    grading: Grading,
+    puzzle: Option<PuzzleState>,
+    attack: Attack,
+    finesse: Finesse,
+    rules: Ruleset,
 }
 
 impl Game {
+    const LINES_PER_LEVEL: u64 = 10;
+    const DIG_VISIBLE_ROWS: u64 = 10;
+    const SURVIVAL_INTERVAL: u128 = 5000;
+    const SURVIVAL_MIN_INTERVAL: u128 = 750;
+
+    // Level the gravity applies from and its strength in 1/256 rows per 60th of a second
+    const MASTER_GRAVITY: [(u64, u128); 30] = [
+        (0, 4),
+        (30, 6),
+        (35, 8),
+        (40, 10),
+        (50, 12),
+        (60, 16),
+        (70, 32),
+        (80, 48),
+        (90, 64),
+        (100, 80),
+        (120, 96),
+        (140, 112),
+        (160, 128),
+        (170, 144),
+        (200, 4),
+        (220, 32),
+        (230, 64),
+        (233, 96),
+        (236, 128),
+        (239, 160),
+        (243, 192),
+        (247, 224),
+        (251, 256),
+        (300, 512),
+        (330, 768),
+        (360, 1024),
+        (400, 1280),
+        (420, 1024),
+        (450, 768),
+        (500, 5120),
+    ];
+
+    // Entry delay, line clear delay in milliseconds and lock delay in samples per 100 levels
+    const MASTER_SECTIONS: [(u128, u128, u8); 10] = [
+        (417, 667, 10),
+        (417, 667, 10),
+        (417, 667, 10),
+        (417, 667, 10),
+        (417, 667, 10),
+        (417, 417, 10),
+        (417, 267, 10),
+        (267, 200, 10),
+        (200, 200, 10),
+        (200, 100, 6),
+    ];
+
     pub fn blocks(&self) -> &[[Colour; Board::WIDTH]; Board::HEIGHT] {
         self.board.blocks()
     }
@@ -98,55 +376,325 @@ impl Game {
         &self.board
     }
 
+    pub fn from_puzzle(puzzle: &Puzzle, rules: &Ruleset) -> Self {
+        Self {
+            puzzle: Some(PuzzleState::new(puzzle)),
+            ..Self::from_board_with_rules(puzzle.board(), GameType::new_puzzle(), rules)
+        }
+    }
+
+    pub fn puzzle_status(&self) -> Option<PuzzleStatus> {
+        self.puzzle.map(|puzzle| puzzle.status())
+    }
+
+    // Pieces placed so far and how many the puzzle allows
+    pub fn puzzle_pieces(&self) -> Option<(u64, u64)> {
+        self.puzzle.map(|puzzle| (puzzle.placed(), puzzle.pieces()))
+    }
+
+    // Applies a sandbox edit in place, false if it was not allowed or garbage pushed blocks out
+    pub fn edit(&mut self, edit: Edit) -> bool {
+        if self.kind.mode() != GameMode::Sandbox {
+            return false;
+        }
+
+        match edit {
+            Edit::Piece(kind) => self.board.set_piece(Piece::new(kind)),
+            Edit::SwapHold => self.board.swap_hold(),
+            Edit::Toggle(x, y) => {
+                if x >= Board::WIDTH || y >= Board::HEIGHT || self.covers(x, y) {
+                    return false;
+                }
+
+                let colour = match self.board.blocks()[y][x] {
+                    Colour::None => Colour::Grey,
+                    _ => Colour::None,
+                };
+
+                self.board.set_cell(x, y, colour);
+            }
+            Edit::Garbage => return self.board.add_garbage(self.garbage.next_hole()),
+        }
+
+        true
+    }
+
+    // Whether the active piece occupies the cell
+    fn covers(&self, x: usize, y: usize) -> bool {
+        let position = self.board.position();
+        let (x, y) = (x as i8 - position.x(), y as i8 - position.y());
+
+        (0..4).contains(&x)
+            && (0..4).contains(&y)
+            && self.board.piece().blocks()[y as usize][x as usize] != Colour::None
+    }
+
+    pub fn attack(&self) -> &Attack {
+        &self.attack
+    }
+
+    pub fn rules(&self) -> &Ruleset {
+        &self.rules
+    }
+
+    // Like from_board, with the board playing by the ruleset
+    pub fn from_board_with_rules(mut board: Board, kind: GameType, rules: &Ruleset) -> Self {
+        board.set_rules(rules);
+
+        Self {
+            rules: rules.clone(),
+            ..Self::from_board(board, kind)
+        }
+    }
+
+    pub fn finesse(&self) -> &Finesse {
+        &self.finesse
+    }
+
+    pub(crate) fn set_attack_table(&mut self, table: AttackTable) {
+        self.attack = Attack::new(table);
+    }
+
+    // Pushes up rows sent by an opponent, returns false if the stack was pushed out the top
+    pub(crate) fn receive_garbage(&mut self, rows: u64) -> bool {
+        let mut alive = true;
+
+        for _ in 0..rows {
+            alive &= self.board.add_garbage(self.garbage.next_hole());
+        }
+
+        alive
+    }
+
+    pub(crate) fn step(&mut self, input: Input, tick: u128) -> TickResult {
+        if let Some(edit) = input.edit {
+            self.edit(edit);
+        }
+
+        let res = self.board.tick_inner(input, tick);
+
+        // A sandbox never ends on its own, the stack is cleared instead
+        if res.kind() == TickType::GameOver && self.kind.mode() == GameMode::Sandbox && !input.quit
+        {
+            let blocks = [[Colour::None; Board::WIDTH]; Board::HEIGHT];
+            let (bag, piece, held) = (self.board.bag(), self.board.piece(), self.board.held());
+
+            self.board = Board::from_position(blocks, bag, piece, held);
+            self.board.set_rules(&self.rules);
+
+            return self.process_tick(res.resume(), tick);
+        }
+
+        self.process_tick(res, tick)
+    }
+
     fn process_tick(&mut self, res: TickResult, tick: u128) -> TickResult {
         if res.kind() == TickType::GameOver {
+            if let Some(puzzle) = &mut self.puzzle {
+                puzzle.fail();
+            }
+
             return res;
         }
 
-        self.score.update(res);
+        let perfect_clear = res.lines() > 0
+            && self
+                .blocks()
+                .iter()
+                .flatten()
+                .all(|tile| *tile == Colour::None);
+
+        self.score.update_with(res, self.rules.scores());
+        self.attack.update(res, perfect_clear);
+
+        if let Some(placement) = self.board.placement().filter(|_| res.placed()) {
+            self.finesse.update(placement, &self.rules);
+        }
 
         match self.kind.mode() {
             GameMode::Time => {
                 if tick / 1000 >= self.kind.count as u128 {
-                    TickResult::new(TickType::GameOver, res.piece(), res.lines())
+                    res.game_over()
                 } else {
                     res
                 }
             }
             GameMode::Lines => {
                 if self.score.lines() >= self.kind.count {
-                    TickResult::new(TickType::GameOver, res.piece(), res.lines())
+                    res.game_over()
+                } else {
+                    res
+                }
+            }
+            GameMode::Dig => {
+                if res.lines() > 0 {
+                    self.feed_garbage();
+                }
+
+                if self.garbage_left() == 0 {
+                    res.game_over()
+                } else {
+                    res
+                }
+            }
+            GameMode::Survival => {
+                self.score.score = (tick / 1000) as u64;
+
+                if tick >= self.next_garbage {
+                    self.next_garbage = tick + Self::survival_interval(tick);
+
+                    if !self.board.add_garbage(self.garbage.next_hole()) {
+                        return res.game_over();
+                    }
+                }
+
+                res
+            }
+            GameMode::Master => {
+                let level = self.score.level;
+
+                self.grading.update(res, level, perfect_clear);
+                self.score.score = self.grading.points();
+
+                if res.placed() {
+                    // Placing a piece does not advance past the end of a section, clears do
+                    let next = if res.lines() > 0 {
+                        level + res.lines() as u64
+                    } else if level % 100 == 99 || level == GameType::MASTER_LEVELS - 1 {
+                        level
+                    } else {
+                        level + 1
+                    }
+                    .min(GameType::MASTER_LEVELS);
+
+                    if next != level {
+                        self.score.level = next;
+                        self.grading.reach(next, tick);
+                        self.master_timing(next);
+                    }
+                }
+
+                if self.score.level >= GameType::MASTER_LEVELS {
+                    res.game_over()
+                } else {
+                    res
+                }
+            }
+            GameMode::Puzzle => match &mut self.puzzle {
+                Some(puzzle) => match puzzle.update(res, self.score.lines(), &self.board) {
+                    PuzzleStatus::InProgress => res,
+                    PuzzleStatus::Solved | PuzzleStatus::Failed => res.game_over(),
+                },
+                None => res,
+            },
+            GameMode::Sandbox | GameMode::Versus => res,
+            GameMode::Marathon => {
+                let level = self.kind.level() + self.score.lines() / Self::LINES_PER_LEVEL;
+
+                if level != self.score.level {
+                    self.score.level = level;
+                    self.board.set_gravity(Self::marathon_gravity(level));
+                }
+
+                if self.kind.count > 0 && self.score.lines() >= self.kind.count {
+                    res.game_over()
                 } else {
                     res
                 }
             }
-            GameMode::Marathon => res,
         }
     }
 
+    // Keeps the garbage stack topped up until every row of the dig has been inserted
+    fn feed_garbage(&mut self) {
+        while self.garbage.inserted() < self.kind.count
+            && (self.board.garbage_rows() as u64) < Self::DIG_VISIBLE_ROWS
+        {
+            self.board.add_garbage(self.garbage.next_hole());
+        }
+    }
+
+    fn master_timing(&mut self, level: u64) {
+        let (_, gravity) = Self::MASTER_GRAVITY
+            .iter()
+            .rev()
+            .find(|(from, _)| level >= *from)
+            .unwrap();
+
+        // 20G, 256 is one row per frame
+        if *gravity >= 5120 {
+            self.board.set_instant_gravity(true);
+        } else {
+            self.board.set_gravity(256 * 1000 / 60 / gravity);
+        }
+
+        let section = (level as usize / 100).min(Self::MASTER_SECTIONS.len() - 1);
+        let (entry, clear, lock) = Self::MASTER_SECTIONS[section];
+
+        self.board.set_delays(entry, clear);
+        self.board.set_lock_samples(lock);
+    }
+
+    // Garbage starts every 5 seconds and comes 250ms sooner every 15 seconds survived
+    fn survival_interval(tick: u128) -> u128 {
+        let speed_up = 250 * (tick / 15000);
+
+        Self::SURVIVAL_INTERVAL
+            .saturating_sub(speed_up)
+            .max(Self::SURVIVAL_MIN_INTERVAL)
+    }
+
+    // Guideline fall speed, (0.8 - (level - 1) * 0.007) ^ (level - 1) seconds per row
+    fn marathon_gravity(level: u64) -> u128 {
+        let level = level.min(20) as f64 - 1.0;
+        let seconds = (0.8 - level * 0.007).powf(level);
+
+        (seconds * 1000.0) as u128
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     pub fn tick(&mut self, input: Input, tick: u128) -> TickResult {
-        let res = self.board.tick(input, tick);
-        self.process_tick(res, tick)
+        self.step(input, tick)
     }
 }
 
 #[wasm_bindgen]
 impl Game {
-    pub fn new(bag: Bag, kind: GameType) -> Self {
-        Self {
-            board: Board::new(bag),
-            score: Score::new(),
-            kind,
-        }
+    pub fn new(bag: Bag, kind: GameType, rules: &Ruleset) -> Self {
+        Self::from_board_with_rules(Board::new(bag), kind, rules)
     }
 
-    pub fn from_board(board: Board, kind: GameType) -> Self {
-        Self {
+    pub fn from_board(mut board: Board, kind: GameType) -> Self {
+        let mut score = Score::new();
+
+        if kind.mode() == GameMode::Marathon {
+            score.level = kind.level();
+            board.set_gravity(Self::marathon_gravity(kind.level()));
+        }
+
+        let mut game = Self {
+            garbage: Garbage::new(board.bag().seed()),
+            next_garbage: Self::survival_interval(0),
+            grading: Grading::new(),
+            puzzle: None,
+            attack: Attack::default(),
+            finesse: Finesse::new(),
+            rules: Ruleset::guideline(),
             board,
-            score: Score::new(),
+            score,
             kind,
+        };
+
+        match kind.mode() {
+            GameMode::Dig => game.feed_garbage(),
+            GameMode::Master => {
+                game.score.level = 0;
+                game.master_timing(0);
+            }
+            _ => {}
         }
+
+        game
     }
 
     pub fn block(&self, x: usize, y: usize) -> Colour {
@@ -173,13 +721,34 @@ impl Game {
         self.score
     }
 
+    pub fn stats(&self) -> PieceStats {
+        self.board.stats()
+    }
+
     pub fn kind(&self) -> GameType {
         self.kind
     }
 
+    pub fn grade(&self) -> Grade {
+        self.grading.grade()
+    }
+
+    pub fn garbage_received(&self) -> u64 {
+        self.garbage.inserted()
+    }
+
+    // Garbage rows still to be cleared, including those not yet on the board
+    pub fn garbage_left(&self) -> u64 {
+        let rows = self.board.garbage_rows() as u64;
+
+        match self.kind.mode() {
+            GameMode::Dig => self.kind.count - self.garbage.inserted() + rows,
+            _ => rows,
+        }
+    }
+
     #[cfg(target_arch = "wasm32")]
     pub fn tick(&mut self, input: Input, tick: u64) -> TickResult {
-        let res = self.board.tick(input, tick);
-        self.process_tick(res, tick as u128)
+        self.step(input, tick as u128)
     }
 }
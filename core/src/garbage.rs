@@ -0,0 +1,40 @@
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::board::Board;
+
+// Picks the hole for each garbage row, seeded from the game seed so replays line up
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Garbage {
+    rng: SmallRng,
+    hole: usize,
+    inserted: u64,
+}
+
+impl Garbage {
+    pub(crate) fn new(seed: u64) -> Self {
+        let mut rng = SmallRng::seed_from_u64(!seed);
+        let hole = rng.gen_range(0..Board::WIDTH);
+
+        Self {
+            rng,
+            hole,
+            inserted: 0,
+        }
+    }
+
+    // A new hole is always in a different column than the previous row's
+    pub(crate) fn next_hole(&mut self) -> usize {
+        let hole = self.hole;
+        let offset = self.rng.gen_range(1..Board::WIDTH);
+
+        self.hole = (self.hole + offset) % Board::WIDTH;
+        self.inserted += 1;
+
+        hole
+    }
+
+    pub(crate) fn inserted(&self) -> u64 {
+        self.inserted
+    }
+}
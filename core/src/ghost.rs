@@ -0,0 +1,103 @@
+use crate::{Game, GameMode, Grade, Playback, Replay, Ruleset};
+
+// A replay raced against, played alongside a live game with the times it reached each line count
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ghost {
+    playback: Playback,
+    splits: Vec<u128>,
+    end: Game,
+}
+
+// How a finished race went, by time when both reached the goal, otherwise by what each reached
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Finish {
+    // Milliseconds behind the ghost, negative when ahead
+    Time(i128),
+    // The player's result first, then the ghost's
+    Lines(u64, u64),
+    Garbage(u64, u64),
+    Score(u64, u64),
+    Grade(Grade, Grade),
+}
+
+impl Ghost {
+    // The replay is played through once up front for its splits
+    pub fn new(replay: Replay, rules: &Ruleset) -> Self {
+        let playback = Playback::new(replay, rules);
+        let mut ahead = playback.clone();
+        let mut splits = vec![0];
+
+        while ahead.tick() < ahead.duration() && ahead.step().is_some() {
+            // Clearing several lines at once reaches each count at the same tick
+            while (splits.len() as u64) <= ahead.game().score().lines() {
+                splits.push(ahead.tick());
+            }
+        }
+
+        let end = ahead.game().clone();
+
+        Self {
+            playback,
+            splits,
+            end,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        self.playback.game()
+    }
+
+    pub fn tick(&self) -> u128 {
+        self.playback.tick()
+    }
+
+    pub fn duration(&self) -> u128 {
+        self.playback.duration()
+    }
+
+    // Plays up to the tick of the live game, stopping where the replay ends
+    pub fn advance(&mut self, tick: u128) {
+        self.playback.seek(tick.min(self.playback.duration()));
+    }
+
+    // The tick the ghost first had this many lines at
+    pub fn split(&self, lines: u64) -> Option<u128> {
+        self.splits.get(lines as usize).copied()
+    }
+
+    // Milliseconds behind the ghost when reaching the lines at this tick, negative when ahead
+    pub fn delta(&self, lines: u64, tick: u128) -> Option<i128> {
+        Some(tick as i128 - self.split(lines)? as i128)
+    }
+
+    // Compares a finished game with where the replay ended
+    pub fn finish(&self, game: &Game, duration: u128) -> Finish {
+        let kind = game.kind();
+
+        match kind.mode() {
+            GameMode::Lines | GameMode::Marathon => {
+                let goal = kind.lines();
+
+                match self.split(goal) {
+                    Some(split) if goal > 0 && game.score().lines() >= goal => {
+                        Finish::Time(duration as i128 - split as i128)
+                    }
+                    _ => Finish::Lines(game.score().lines(), self.end.score().lines()),
+                }
+            }
+            GameMode::Dig => {
+                if game.garbage_left() == 0 && self.end.garbage_left() == 0 {
+                    Finish::Time(duration as i128 - self.duration() as i128)
+                } else {
+                    let cleared = |game: &Game| kind.rows() - game.garbage_left();
+
+                    Finish::Garbage(cleared(game), cleared(&self.end))
+                }
+            }
+            // Lasting longer is ahead
+            GameMode::Survival => Finish::Time(self.duration() as i128 - duration as i128),
+            GameMode::Master => Finish::Grade(game.grade(), self.end.grade()),
+            _ => Finish::Score(game.score().score(), self.end.score().score()),
+        }
+    }
+}
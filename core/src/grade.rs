@@ -0,0 +1,173 @@
+use std::fmt::Display;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::board::TickResult;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[wasm_bindgen]
+#[repr(u8)]
+pub enum Grade {
+    Nine = 0,
+    Eight,
+    Seven,
+    Six,
+    Five,
+    Four,
+    Three,
+    Two,
+    One,
+    S1,
+    S2,
+    S3,
+    S4,
+    S5,
+    S6,
+    S7,
+    S8,
+    S9,
+    GrandMaster,
+}
+
+// Points needed for each grade from 8 through S9
+const GRADE_POINTS: [u64; 17] = [
+    400, 800, 1400, 2000, 3500, 5500, 8000, 12000, 16000, 22000, 30000, 40000, 52000, 66000, 82000,
+    100000, 120000,
+];
+
+// Level, points and milliseconds a grand master run has to pass through
+const CHECKPOINTS: [(u64, u64, u128); 3] = [
+    (300, 12000, 255_000),
+    (500, 40000, 450_000),
+    (999, 126000, 810_000),
+];
+
+impl Grade {
+    pub(crate) fn new(grade: u8) -> Result<Self, ()> {
+        const GRADES: [Grade; 19] = [
+            Grade::Nine,
+            Grade::Eight,
+            Grade::Seven,
+            Grade::Six,
+            Grade::Five,
+            Grade::Four,
+            Grade::Three,
+            Grade::Two,
+            Grade::One,
+            Grade::S1,
+            Grade::S2,
+            Grade::S3,
+            Grade::S4,
+            Grade::S5,
+            Grade::S6,
+            Grade::S7,
+            Grade::S8,
+            Grade::S9,
+            Grade::GrandMaster,
+        ];
+
+        GRADES.get(grade as usize).copied().ok_or(())
+    }
+
+    fn from_points(points: u64) -> Self {
+        let grade = GRADE_POINTS.iter().filter(|p| points >= **p).count();
+
+        Self::new(grade as u8).unwrap()
+    }
+}
+
+impl Display for Grade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let grade = *self as u8;
+
+        match self {
+            Grade::GrandMaster => write!(f, "GM"),
+            Grade::S1
+            | Grade::S2
+            | Grade::S3
+            | Grade::S4
+            | Grade::S5
+            | Grade::S6
+            | Grade::S7
+            | Grade::S8
+            | Grade::S9 => write!(f, "S{}", grade - Grade::One as u8),
+            _ => write!(f, "{}", 9 - grade),
+        }
+    }
+}
+
+// Master mode scoring, clears are worth more the higher the level and the longer the combo
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[wasm_bindgen]
+pub struct Grading {
+    points: u64,
+    combo: u64,
+    checkpoint: usize,
+    qualified: bool,
+    grade: Grade,
+}
+
+impl Default for Grading {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Grading {
+    pub(crate) fn update(&mut self, tick: TickResult, level: u64, perfect_clear: bool) {
+        if !tick.placed() {
+            return;
+        }
+
+        let lines = tick.lines() as u64;
+
+        if lines == 0 {
+            self.combo = 1;
+            return;
+        }
+
+        self.combo += 2 * lines - 2;
+
+        let bravo = if perfect_clear { 4 } else { 1 };
+        self.points += (level + lines).div_ceil(4) * lines * self.combo * bravo;
+
+        self.grade = self.grade.max(Grade::from_points(self.points));
+    }
+
+    // Called as the level goes up, missing a checkpoint rules out grand master
+    pub(crate) fn reach(&mut self, level: u64, time: u128) {
+        while let Some(&(at, points, limit)) = CHECKPOINTS.get(self.checkpoint) {
+            if level < at {
+                break;
+            }
+
+            self.qualified &= self.points >= points && time <= limit;
+            self.checkpoint += 1;
+        }
+
+        if self.checkpoint == CHECKPOINTS.len() && self.qualified {
+            self.grade = Grade::GrandMaster;
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Grading {
+    pub fn new() -> Self {
+        Self {
+            points: 0,
+            combo: 1,
+            checkpoint: 0,
+            qualified: true,
+            grade: Grade::Nine,
+        }
+    }
+
+    pub fn points(&self) -> u64 {
+        self.points
+    }
+
+    pub fn grade(&self) -> Grade {
+        self.grade
+    }
+}
@@ -1,5 +1,7 @@
 use wasm_bindgen::prelude::wasm_bindgen;
 
+use crate::sandbox::Edit;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[wasm_bindgen]
 pub enum InputDirection {
@@ -40,6 +42,8 @@ pub struct Input {
     pub soft_drop: bool,
     pub rotation: InputRotation,
     pub direction: InputDirection,
+    #[wasm_bindgen(skip)]
+    pub edit: Option<Edit>,
 }
 
 #[wasm_bindgen]
@@ -59,6 +63,32 @@ impl Input {
             soft_drop,
             rotation,
             direction,
+            edit: None,
+        }
+    }
+}
+
+impl Input {
+    /*
+     * Inputs of frames sharing a tick, presses add up and the later movement
+     * wins. Games take one input a millisecond, so only legacy and imported
+     * replays have such frames, and a press repeated within the tick counts once
+     */
+    pub fn merge(self, later: Input) -> Input {
+        Input {
+            hold: self.hold || later.hold,
+            quit: self.quit || later.quit,
+            hard_drop: self.hard_drop || later.hard_drop,
+            soft_drop: self.soft_drop || later.soft_drop,
+            rotation: match later.rotation {
+                InputRotation::None => self.rotation,
+                rotation => rotation,
+            },
+            direction: match later.direction {
+                InputDirection::None => self.direction,
+                direction => direction,
+            },
+            edit: later.edit.or(self.edit),
         }
     }
 }
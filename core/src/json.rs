@@ -0,0 +1,243 @@
+// Just enough JSON to write and read back replays, numbers are kept as written
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+// A string in quotes with everything JSON does not allow escaped
+pub fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+// Arrays and objects recurse, anything nested deeper is refused before it overflows the stack
+const MAX_DEPTH: usize = 64;
+
+impl Json {
+    // Parses a whole document, the error is the line the parser stopped at
+    pub(crate) fn parse(s: &str) -> Result<Json, usize> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            at: 0,
+            depth: 0,
+        };
+
+        let value = parser.value();
+        parser.skip_whitespace();
+
+        match value {
+            Some(value) if parser.at == parser.chars.len() => Ok(value),
+            _ => Err(parser.line()),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    at: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        1 + self.chars[..self.at.min(self.chars.len())]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.at).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.at += 1;
+
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, word: &str) -> Option<()> {
+        for c in word.chars() {
+            if self.next()? != c {
+                return None;
+            }
+        }
+
+        Some(())
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.skip_whitespace();
+
+        match self.peek()? {
+            'n' => self.expect("null").map(|_| Json::Null),
+            't' => self.expect("true").map(|_| Json::Bool(true)),
+            'f' => self.expect("false").map(|_| Json::Bool(false)),
+            '"' => self.string().map(Json::String),
+            '[' => self.nested(Self::array),
+            '{' => self.nested(Self::object),
+            '-' | '0'..='9' => self.number(),
+            _ => None,
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Option<Json>) -> Option<Json> {
+        if self.depth == MAX_DEPTH {
+            return None;
+        }
+
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+
+        value
+    }
+
+    fn number(&mut self) -> Option<Json> {
+        let start = self.at;
+
+        while matches!(self.peek(), Some('-' | '+' | '.' | 'e' | 'E' | '0'..='9')) {
+            self.at += 1;
+        }
+
+        let number: String = self.chars[start..self.at].iter().collect();
+
+        number.parse::<f64>().ok().map(|_| Json::Number(number))
+    }
+
+    fn hex(&mut self) -> Option<u32> {
+        let mut value = 0;
+
+        for _ in 0..4 {
+            value = value * 16 + self.next()?.to_digit(16)?;
+        }
+
+        Some(value)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.expect("\"")?;
+
+        let mut s = String::new();
+
+        loop {
+            match self.next()? {
+                '"' => return Some(s),
+                '\\' => s.push(match self.next()? {
+                    '"' => '"',
+                    '\\' => '\\',
+                    '/' => '/',
+                    'b' => '\x08',
+                    'f' => '\x0c',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'u' => {
+                        let mut code = self.hex()?;
+
+                        // Characters outside the basic plane come as a surrogate pair
+                        if (0xd800..0xdc00).contains(&code) {
+                            self.expect("\\u")?;
+                            let low = self.hex()?;
+                            code = 0x10000 + ((code - 0xd800) << 10) + low.checked_sub(0xdc00)?;
+                        }
+
+                        char::from_u32(code)?
+                    }
+                    _ => return None,
+                }),
+                c if (c as u32) < 0x20 => return None,
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Option<Json> {
+        self.expect("[")?;
+        self.skip_whitespace();
+
+        let mut values = Vec::new();
+
+        if self.peek()? == ']' {
+            self.at += 1;
+            return Some(Json::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+
+            match self.next()? {
+                ',' => continue,
+                ']' => return Some(Json::Array(values)),
+                _ => return None,
+            }
+        }
+    }
+
+    fn object(&mut self) -> Option<Json> {
+        self.expect("{")?;
+        self.skip_whitespace();
+
+        let mut fields = Vec::new();
+
+        if self.peek()? == '}' {
+            self.at += 1;
+            return Some(Json::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+
+            let key = self.string()?;
+
+            self.skip_whitespace();
+            self.expect(":")?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+
+            match self.next()? {
+                ',' => continue,
+                '}' => return Some(Json::Object(fields)),
+                _ => return None,
+            }
+        }
+    }
+}
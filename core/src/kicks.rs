@@ -158,6 +158,15 @@ pub const I_KICKS: [[[Point; 5]; 2]; 4] = [
     ],
 ];
 
+// Rotation systems without wall kick tables only try moving a column to each side, or nothing
+pub const BASIC_KICKS: [Point; 3] = [
+    Point::constant(0, 0),
+    Point::constant(1, 0),
+    Point::constant(-1, 0),
+];
+
+pub const NO_KICKS: [Point; 1] = [Point::constant(0, 0)];
+
 pub const KICKS_180: [[Point; 6]; 4] = [
     [
         Point::constant(0, 0),
@@ -1,22 +1,57 @@
+mod analysis;
+mod attack;
 mod bag;
 mod board;
+mod clock;
 mod colour;
+mod export;
+mod finesse;
 mod game;
+mod garbage;
+mod ghost;
+mod grade;
 mod input;
+pub mod json;
 mod kicks;
+mod lockstep;
 mod piece;
+mod playback;
 mod point;
+mod puzzle;
 mod replay;
+mod ruleset;
+mod sandbox;
 mod score;
+mod simulator;
+mod stats;
 mod tests;
+mod verify;
+mod versus;
 
 pub use {
+    analysis::{Analysis, PieceTime},
+    attack::{Attack, AttackTable, GarbageQueue},
     bag::Bag,
     board::{Board, TickResult, TickType},
+    clock::Clock,
     colour::Colour,
-    game::{Game, GameMode, GameType},
+    export::ImportError,
+    finesse::{Finesse, FinesseResult, Placement},
+    game::{Game, GameMode, GameType, GameTypeError},
+    ghost::{Finish, Ghost},
+    grade::{Grade, Grading},
     input::{Input, InputDirection, InputRotation},
-    piece::Piece,
-    replay::{Frame, Recorder, Replay},
+    lockstep::{Lockstep, LockstepError, Message},
+    piece::{Piece, PieceType},
+    playback::Playback,
+    point::Point,
+    puzzle::{Goal, Puzzle, PuzzleError, PuzzleStatus},
+    replay::{Frame, Metadata, Recorder, Replay, ReplayError},
+    ruleset::{Kicks, Ruleset, RulesetError},
+    sandbox::Edit,
     score::Score,
+    simulator::Simulator,
+    stats::PieceStats,
+    verify::{Mismatch, Verification},
+    versus::Match,
 };
@@ -0,0 +1,313 @@
+use std::{collections::VecDeque, fmt::Display, io::Read};
+
+use crate::{
+    attack::AttackTable, replay::RecorderFrame, Bag, Board, Input, Match, Metadata, Recorder,
+    Ruleset, TickType,
+};
+
+// Peers refuse to play against a different version of the messages below
+const PROTOCOL: u8 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message {
+    // Sent once by the host, the match both peers play, rules are sent as their hash
+    Hello { seed: u64, delay: u64, rules: u64 },
+    // One player's input for the next step, encoded like a replay frame
+    Input(Input),
+    // Rows a player sent on a step, checked against the receiving peer's simulation
+    Attack { step: u64, rows: u64 },
+}
+
+impl Message {
+    const HELLO: u8 = 0;
+    const INPUT: u8 = 1;
+    const ATTACK: u8 = 2;
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+
+        match *self {
+            Message::Hello { seed, delay, rules } => {
+                buffer.push(Self::HELLO);
+                buffer.push(PROTOCOL);
+                buffer.extend_from_slice(&seed.to_be_bytes());
+                buffer.extend_from_slice(&delay.to_be_bytes());
+                buffer.extend_from_slice(&rules.to_be_bytes());
+            }
+            Message::Input(input) => {
+                let frame = RecorderFrame::new(Lockstep::STEP as u64, input);
+
+                buffer.push(Self::INPUT);
+                buffer.extend_from_slice(&(frame.time as u16).to_be_bytes());
+                buffer.push(frame.input);
+            }
+            Message::Attack { step, rows } => {
+                buffer.push(Self::ATTACK);
+                buffer.extend_from_slice(&step.to_be_bytes());
+                buffer.extend_from_slice(&rows.to_be_bytes());
+            }
+        }
+
+        buffer
+    }
+
+    // Blocks until a whole message has been read
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, LockstepError> {
+        let mut byte = [0u8];
+        let mut num = [0u8; 8];
+
+        let mut read = |buf: &mut [u8]| match reader.read_exact(buf) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(LockstepError::Disconnected),
+        };
+
+        read(&mut byte)?;
+
+        match byte[0] {
+            Self::HELLO => {
+                read(&mut byte)?;
+
+                if byte[0] != PROTOCOL {
+                    return Err(LockstepError::ProtocolMismatch);
+                }
+
+                read(&mut num)?;
+                let seed = u64::from_be_bytes(num);
+                read(&mut num)?;
+                let delay = u64::from_be_bytes(num);
+                read(&mut num)?;
+                let rules = u64::from_be_bytes(num);
+
+                Ok(Message::Hello { seed, delay, rules })
+            }
+            Self::INPUT => {
+                let mut frame = [0u8; 3];
+                read(&mut frame)?;
+
+                Ok(Message::Input(RecorderFrame::from(frame).input()))
+            }
+            Self::ATTACK => {
+                read(&mut num)?;
+                let step = u64::from_be_bytes(num);
+                read(&mut num)?;
+                let rows = u64::from_be_bytes(num);
+
+                Ok(Message::Attack { step, rows })
+            }
+            _ => Err(LockstepError::UnknownMessage),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LockstepError {
+    Disconnected,
+    ProtocolMismatch,
+    RulesMismatch,
+    UnknownMessage,
+    UnexpectedHello,
+    Desync(u64),
+}
+
+impl Display for LockstepError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockstepError::Disconnected => write!(f, "Connection to the other player was lost"),
+            LockstepError::ProtocolMismatch => {
+                write!(f, "The other player is running an incompatible version")
+            }
+            LockstepError::RulesMismatch => {
+                write!(f, "The other player is playing by a different ruleset")
+            }
+            LockstepError::UnknownMessage => write!(f, "Received an unknown message"),
+            LockstepError::UnexpectedHello => write!(f, "Received a second greeting"),
+            LockstepError::Desync(step) => write!(f, "Games went out of sync on step {}", step),
+        }
+    }
+}
+
+// Runs a versus match in fixed steps, each step waits until the inputs of both players are known
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lockstep {
+    versus: Match,
+    seed: u64,
+    delay: u64,
+    local: usize,
+    step: u64,
+    inputs: [VecDeque<Input>; 2],
+    recorders: [Recorder; 2],
+    expected: VecDeque<(u64, u64)>,
+    reported: VecDeque<(u64, u64)>,
+}
+
+impl Lockstep {
+    // Milliseconds of game time per step
+    pub const STEP: u128 = 10;
+    // Steps an input waits before it is played, hides the round trip to the other player
+    pub const INPUT_DELAY: usize = 4;
+
+    pub fn new(seed: u64, delay: u64, local: usize, rules: &Ruleset) -> Self {
+        let boards = [Board::new(Bag::new(seed)), Board::new(Bag::new(seed))];
+        let versus = Match::with_rules(boards, AttackTable::default(), delay as u128, rules);
+        let recorders = [
+            Recorder::create(seed, versus.player(0), 0),
+            Recorder::create(seed, versus.player(1), 0),
+        ];
+        let delayed = VecDeque::from(vec![Input::default(); Self::INPUT_DELAY]);
+
+        Self {
+            versus,
+            seed,
+            delay,
+            local,
+            step: 0,
+            inputs: [delayed.clone(), delayed],
+            recorders,
+            expected: VecDeque::new(),
+            reported: VecDeque::new(),
+        }
+    }
+
+    // Joins the match a host greeted us with, as long as both sides play by the same rules
+    pub fn from_hello(
+        message: Message,
+        local: usize,
+        rules: &Ruleset,
+    ) -> Result<Self, LockstepError> {
+        match message {
+            Message::Hello { rules: hash, .. } if hash != rules.hash() => {
+                Err(LockstepError::RulesMismatch)
+            }
+            Message::Hello { seed, delay, .. } => Ok(Self::new(seed, delay, local, rules)),
+            _ => Err(LockstepError::UnknownMessage),
+        }
+    }
+
+    pub fn hello(&self) -> Message {
+        Message::Hello {
+            seed: self.seed,
+            delay: self.delay,
+            rules: self.versus.player(0).rules().hash(),
+        }
+    }
+
+    // Queues the local player's input for the next step, returns the message for the peer
+    pub fn local(&mut self, input: Input) -> Message {
+        self.inputs[self.local].push_back(input);
+
+        Message::Input(input)
+    }
+
+    pub fn receive(&mut self, message: Message) -> Result<(), LockstepError> {
+        match message {
+            Message::Hello { .. } => return Err(LockstepError::UnexpectedHello),
+            Message::Input(input) => self.inputs[1 - self.local].push_back(input),
+            Message::Attack { step, rows } => self.reported.push_back((step, rows)),
+        }
+
+        self.check()
+    }
+
+    /*
+     * Both peers simulate both players, so the attacks each reports have to
+     * line up. Reports arrive in step order, once a step has been played
+     * here a report for it has to be the next attack expected, and an
+     * expected attack left behind by a report for a later step never came
+     */
+    fn check(&mut self) -> Result<(), LockstepError> {
+        while let Some(&(step, rows)) = self.reported.front().filter(|(step, _)| *step < self.step)
+        {
+            self.reported.pop_front();
+
+            match self.expected.pop_front() {
+                Some(expected) if expected == (step, rows) => {}
+                Some((expected, _)) if expected < step => {
+                    return Err(LockstepError::Desync(expected))
+                }
+                _ => return Err(LockstepError::Desync(step)),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Plays every step both inputs are known for, returns the attacks to report to the peer
+    pub fn advance(&mut self) -> Result<Vec<Message>, LockstepError> {
+        let mut messages = Vec::new();
+
+        while !self.versus.over() && self.inputs.iter().all(|inputs| !inputs.is_empty()) {
+            let mut inputs = [Input::default(); 2];
+
+            for (input, queue) in inputs.iter_mut().zip(self.inputs.iter_mut()) {
+                *input = queue.pop_front().unwrap();
+            }
+
+            let start = self.step as u128 * Self::STEP;
+
+            for tick in (start + 1)..=(start + Self::STEP) {
+                for (recorder, input) in self.recorders.iter_mut().zip(inputs) {
+                    recorder.record_input(input, tick);
+                }
+
+                let results = self.versus.tick(inputs, tick);
+                inputs = [Input::default(); 2];
+
+                for (player, res) in results.iter().enumerate() {
+                    if !res.placed() || res.kind() == TickType::GameOver {
+                        continue;
+                    }
+
+                    let rows = self.versus.player(player).attack().last();
+
+                    if rows == 0 {
+                        continue;
+                    } else if player == self.local {
+                        messages.push(Message::Attack {
+                            step: self.step,
+                            rows,
+                        });
+                    } else {
+                        self.expected.push_back((self.step, rows));
+                    }
+                }
+
+                if self.versus.over() {
+                    break;
+                }
+            }
+
+            self.step += 1;
+        }
+
+        self.check()?;
+
+        Ok(messages)
+    }
+
+    pub fn versus(&self) -> &Match {
+        &self.versus
+    }
+
+    // The player controlled on this side, 0 for the host
+    pub fn local_player(&self) -> usize {
+        self.local
+    }
+
+    // Milliseconds of the match played so far
+    pub fn duration(&self) -> u64 {
+        (self.step as u128 * Self::STEP) as u64
+    }
+
+    // Metadata goes with the local player's recording, which writes the header
+    pub fn set_metadata(&mut self, key: Metadata, value: String) {
+        self.recorders[0].set_metadata(key, value)
+    }
+
+    // A versus replay holding the inputs of both players
+    pub fn raw(self, username: &str, end_time: i64) -> Vec<u8> {
+        let duration = self.duration();
+        let [one, two] = self.recorders;
+
+        one.raw_versus(two, username, &self.versus, duration, end_time)
+    }
+}
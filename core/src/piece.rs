@@ -14,6 +14,18 @@ pub enum PieceType {
     Z,
 }
 
+impl PieceType {
+    pub const ALL: [PieceType; 7] = [
+        PieceType::I,
+        PieceType::J,
+        PieceType::L,
+        PieceType::O,
+        PieceType::S,
+        PieceType::T,
+        PieceType::Z,
+    ];
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[wasm_bindgen]
 pub struct PieceBody([[Colour; 4]; 4]);
@@ -207,6 +219,18 @@ impl Piece {
         self.kind
     }
 
+    pub fn colour(&self) -> Colour {
+        match self.kind {
+            PieceType::I => Cyan,
+            PieceType::J => Blue,
+            PieceType::L => Orange,
+            PieceType::O => Yellow,
+            PieceType::S => Green,
+            PieceType::T => Purple,
+            PieceType::Z => Red,
+        }
+    }
+
     pub fn rotation(&self) -> u8 {
         self.rotation
     }
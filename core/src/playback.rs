@@ -0,0 +1,109 @@
+use crate::{simulator::State, Game, Replay, Ruleset, Simulator, TickResult};
+
+// A copy of the simulation taken every so often, so seeking back only replays from the closest one
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Keyframe {
+    tick: u128,
+    pieces: u64,
+    state: State,
+}
+
+// A single player replay that can be stepped through, seeked and rewound
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Playback {
+    simulator: Simulator,
+    duration: u128,
+    pieces: u64,
+    keyframes: Vec<Keyframe>,
+}
+
+impl Playback {
+    // Milliseconds between keyframes
+    pub const KEYFRAME_INTERVAL: u128 = 1000;
+
+    pub fn new(replay: Replay, rules: &Ruleset) -> Self {
+        let duration = replay.duration() as u128;
+        let simulator = Simulator::new(replay, rules);
+
+        Self {
+            keyframes: vec![Keyframe {
+                tick: 0,
+                pieces: 0,
+                state: simulator.state().clone(),
+            }],
+            simulator,
+            duration,
+            pieces: 0,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        self.simulator.game()
+    }
+
+    pub fn tick(&self) -> u128 {
+        self.simulator.tick()
+    }
+
+    // Length of the recorded game in milliseconds
+    pub fn duration(&self) -> u128 {
+        self.duration
+    }
+
+    // Pieces placed so far
+    pub fn pieces(&self) -> u64 {
+        self.pieces
+    }
+
+    pub fn over(&self) -> bool {
+        self.simulator.over()
+    }
+
+    // Plays one millisecond, doing nothing once the game is over
+    pub fn step(&mut self) -> Option<TickResult> {
+        let res = self.simulator.step()?;
+
+        self.pieces += res.placed() as u64;
+
+        let last = self.keyframes.last().map_or(0, |keyframe| keyframe.tick);
+
+        if self.tick() >= last + Self::KEYFRAME_INTERVAL {
+            self.keyframes.push(Keyframe {
+                tick: self.tick(),
+                pieces: self.pieces,
+                state: self.simulator.state().clone(),
+            });
+        }
+
+        Some(res)
+    }
+
+    // Plays up to a tick, going back to the closest keyframe before it if it has passed
+    pub fn seek(&mut self, tick: u128) {
+        if tick < self.tick() {
+            self.restore(|keyframe| keyframe.tick <= tick);
+        }
+
+        while self.tick() < tick && self.step().is_some() {}
+    }
+
+    // Plays up to when the nth piece enters, counting from 1
+    pub fn seek_piece(&mut self, piece: u64) {
+        let placed = piece.saturating_sub(1);
+
+        if placed <= self.pieces {
+            self.restore(|keyframe| keyframe.pieces < placed || keyframe.tick == 0);
+        }
+
+        while self.pieces < placed && self.step().is_some() {}
+    }
+
+    // Goes back to the latest keyframe the condition holds for, the first one always exists
+    fn restore<F: Fn(&Keyframe) -> bool>(&mut self, condition: F) {
+        let index = self.keyframes.iter().rposition(condition).unwrap_or(0);
+        let keyframe = self.keyframes[index].clone();
+
+        self.pieces = keyframe.pieces;
+        self.simulator.restore(keyframe.state);
+    }
+}
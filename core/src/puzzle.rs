@@ -0,0 +1,321 @@
+use std::{fmt::Display, str::FromStr};
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    board::{TickResult, TickType},
+    piece::PieceType,
+    Bag, Board, Colour, Piece,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    Lines(u64),
+    TSpinDouble,
+    PerfectClear,
+}
+
+impl Display for Goal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Goal::Lines(1) => write!(f, "Clear 1 line"),
+            Goal::Lines(lines) => write!(f, "Clear {} lines", lines),
+            Goal::TSpinDouble => write!(f, "T-spin double"),
+            Goal::PerfectClear => write!(f, "Perfect clear"),
+        }
+    }
+}
+
+impl FromStr for Goal {
+    type Err = PuzzleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+
+        let goal = match words
+            .next()
+            .map(|word| word.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("lines") => match words.next().map(str::parse) {
+                Some(Ok(lines)) if lines > 0 => Goal::Lines(lines),
+                _ => return Err(PuzzleError::InvalidGoal),
+            },
+            Some("tsd") => Goal::TSpinDouble,
+            Some("pc" | "perfect") => Goal::PerfectClear,
+            _ => return Err(PuzzleError::InvalidGoal),
+        };
+
+        match words.next() {
+            Some(_) => Err(PuzzleError::InvalidGoal),
+            None => Ok(goal),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PuzzleError {
+    InvalidRow,
+    InvalidPiece,
+    InvalidGoal,
+    InvalidOption,
+    MissingQueue,
+    MissingGoal,
+}
+
+impl Display for PuzzleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PuzzleError::InvalidRow => "Board rows must be 10 wide and wrapped in |",
+                PuzzleError::InvalidPiece => "Pieces must be one of I, J, L, O, S, T or Z",
+                PuzzleError::InvalidGoal => "Goal must be one of lines <n>, tsd or pc",
+                PuzzleError::InvalidOption => "Expected name, queue, hold, goal or pieces",
+                PuzzleError::MissingQueue => "Puzzle has no queue",
+                PuzzleError::MissingGoal => "Puzzle has no goal",
+            }
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[wasm_bindgen]
+pub enum PuzzleStatus {
+    InProgress,
+    Solved,
+    Failed,
+}
+
+fn piece_type(c: char) -> Result<PieceType, PuzzleError> {
+    match c.to_ascii_uppercase() {
+        'I' => Ok(PieceType::I),
+        'J' => Ok(PieceType::J),
+        'L' => Ok(PieceType::L),
+        'O' => Ok(PieceType::O),
+        'S' => Ok(PieceType::S),
+        'T' => Ok(PieceType::T),
+        'Z' => Ok(PieceType::Z),
+        _ => Err(PuzzleError::InvalidPiece),
+    }
+}
+
+/*
+ * A puzzle is written as options followed by the board, one puzzle per
+ * section of a pack with sections separated by ---
+ *
+ * name = TSD
+ * queue = TIO
+ * hold = L
+ * goal = tsd
+ * pieces = 2
+ * |          |
+ * |##   #####|
+ * |#   ######|
+ * |## #######|
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Puzzle {
+    name: String,
+    rows: Vec<String>,
+    queue: Vec<PieceType>,
+    hold: Option<PieceType>,
+    goal: Goal,
+    pieces: Option<u64>,
+}
+
+impl Puzzle {
+    pub fn pack(s: &str) -> Result<Vec<Self>, PuzzleError> {
+        let mut sections = vec![String::default()];
+
+        for line in s.lines() {
+            if line.trim() == "---" {
+                sections.push(String::default());
+            } else {
+                let section = sections.last_mut().unwrap();
+                section.push_str(line);
+                section.push('\n');
+            }
+        }
+
+        sections
+            .iter()
+            .filter(|section| !section.trim().is_empty())
+            .map(|section| section.parse())
+            .collect()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn goal(&self) -> Goal {
+        self.goal
+    }
+
+    // Most pieces that may be placed, the queue plus the held piece unless limited further
+    pub fn pieces(&self) -> u64 {
+        let available = self.queue.len() as u64 + self.hold.is_some() as u64;
+
+        self.pieces.unwrap_or(available).min(available)
+    }
+
+    pub fn board(&self) -> Board {
+        let rows: Vec<&str> = self.rows.iter().map(String::as_str).collect();
+        let board = Board::from_strs(&rows, Bag::from_queue(&self.queue, 0));
+
+        Board::from_position(
+            *board.blocks(),
+            board.bag(),
+            board.piece(),
+            self.hold.map(Piece::new),
+        )
+    }
+}
+
+impl FromStr for Puzzle {
+    type Err = PuzzleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut name = String::default();
+        let mut rows = Vec::new();
+        let mut queue = Vec::new();
+        let mut hold = None;
+        let mut goal = None;
+        let mut pieces = None;
+
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            if let Some(row) = line.trim_end().strip_prefix('|') {
+                match row.strip_suffix('|') {
+                    // Boards are built byte by byte, so anything but ASCII would not fit
+                    Some(row) if row.is_ascii() && row.len() == Board::WIDTH => {
+                        rows.push(row.to_string())
+                    }
+                    _ => return Err(PuzzleError::InvalidRow),
+                }
+
+                continue;
+            }
+
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+
+            let (option, value) = match line.split_once('=') {
+                Some((option, value)) => (option.trim(), value.trim()),
+                None => return Err(PuzzleError::InvalidOption),
+            };
+
+            match option {
+                "name" => name = value.to_string(),
+                "queue" => {
+                    queue = value
+                        .chars()
+                        .filter(|c| !c.is_whitespace())
+                        .map(piece_type)
+                        .collect::<Result<_, _>>()?
+                }
+                "hold" => {
+                    let mut chars = value.chars();
+
+                    hold = match (chars.next(), chars.next()) {
+                        (Some(c), None) => Some(piece_type(c)?),
+                        _ => return Err(PuzzleError::InvalidPiece),
+                    }
+                }
+                "goal" => goal = Some(value.parse()?),
+                "pieces" => match value.parse() {
+                    Ok(count) => pieces = Some(count),
+                    Err(_) => return Err(PuzzleError::InvalidOption),
+                },
+                _ => return Err(PuzzleError::InvalidOption),
+            }
+        }
+
+        if rows.len() >= Board::HEIGHT {
+            return Err(PuzzleError::InvalidRow);
+        }
+
+        if queue.is_empty() {
+            return Err(PuzzleError::MissingQueue);
+        }
+
+        Ok(Self {
+            name,
+            rows,
+            queue,
+            hold,
+            goal: goal.ok_or(PuzzleError::MissingGoal)?,
+            pieces,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PuzzleState {
+    goal: Goal,
+    pieces: u64,
+    placed: u64,
+    status: PuzzleStatus,
+}
+
+impl PuzzleState {
+    pub(crate) fn new(puzzle: &Puzzle) -> Self {
+        Self {
+            goal: puzzle.goal(),
+            pieces: puzzle.pieces(),
+            placed: 0,
+            status: PuzzleStatus::InProgress,
+        }
+    }
+
+    pub(crate) fn update(&mut self, res: TickResult, lines: u64, board: &Board) -> PuzzleStatus {
+        if self.status != PuzzleStatus::InProgress || !res.placed() {
+            return self.status;
+        }
+
+        self.placed += 1;
+
+        let solved = match self.goal {
+            Goal::Lines(goal) => lines >= goal,
+            Goal::TSpinDouble => {
+                res.kind() == TickType::Spin && res.piece() == PieceType::T && res.lines() == 2
+            }
+            Goal::PerfectClear => {
+                res.lines() > 0
+                    && board
+                        .blocks()
+                        .iter()
+                        .flatten()
+                        .all(|tile| *tile == Colour::None)
+            }
+        };
+
+        if solved {
+            self.status = PuzzleStatus::Solved;
+        } else if self.placed >= self.pieces {
+            self.status = PuzzleStatus::Failed;
+        }
+
+        self.status
+    }
+
+    pub(crate) fn fail(&mut self) {
+        if self.status == PuzzleStatus::InProgress {
+            self.status = PuzzleStatus::Failed;
+        }
+    }
+
+    pub(crate) fn status(&self) -> PuzzleStatus {
+        self.status
+    }
+
+    pub(crate) fn placed(&self) -> u64 {
+        self.placed
+    }
+
+    pub(crate) fn pieces(&self) -> u64 {
+        self.pieces
+    }
+}
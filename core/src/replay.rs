@@ -1,58 +1,239 @@
-use std::{
-    collections::VecDeque,
-    io::{BufRead, BufReader, Read},
-};
+use std::collections::VecDeque;
 
 use wasm_bindgen::prelude::wasm_bindgen;
 
 use crate::{
     input::{Input, InputDirection, InputRotation},
-    Game, GameMode, GameType, Score,
+    ruleset::Ruleset,
+    sandbox::Edit,
+    Game, GameMode, GameType, Grade, Match, Score,
 };
 
-const VERSION: u8 = 1;
+/*
+ * The version byte goes up with every change to the format. Version 1 is
+ * the original, 2 added levels, 3 the master grade and 4 the ruleset hash,
+ * all starting with the username up to a newline and without a checksum.
+ * The checksummed format with magic bytes and a length prefixed username,
+ * asked for as "v2", came after those and so is version 5. Version 6 ticks
+ * every millisecond, 7 writes frame times as varints and 8 adds metadata
+ */
+pub(crate) const VERSION: u8 = 8;
+
+// Starts every replay from version 5 on, no username of the older format can start with 0x89
+const MAGIC: [u8; 4] = [0x89, b'R', b'T', b'R'];
+
+// The last version that began with the username up to a newline
+const LEGACY_VERSION: u8 = 4;
+
+// The first version with the magic and a checksum, and the last that did not tick every millisecond
+const CHECKSUM_VERSION: u8 = 5;
+
+// The first version where every millisecond of a game was ticked, even when drawing fell behind
+pub(crate) const VERIFIABLE_VERSION: u8 = 6;
+
+// The first version with frame times as varints, before they were two bytes each
+const COMPACT_VERSION: u8 = 7;
+
+// The first version with a metadata section after the ruleset hash
+const METADATA_VERSION: u8 = 8;
+
+// CRC-32 as used by zip and png
+pub(crate) fn crc32(buffer: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for byte in buffer {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb88320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
+
+// What a metadata entry holds, each is written as its tag, a u16 length and
+// the value as text so readers can skip tags they do not know
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[wasm_bindgen]
+#[repr(u8)]
+pub enum Metadata {
+    // Name and version of the program the replay was recorded by
+    Client = 1,
+    // Keys and frame rate, in the form of the client's config
+    Handling = 2,
+    // The whole ruleset in the format of a rules file
+    Ruleset = 3,
+    Platform = 4,
+    Notes = 5,
+}
+
+impl Metadata {
+    pub const ALL: [Metadata; 5] = [
+        Metadata::Client,
+        Metadata::Handling,
+        Metadata::Ruleset,
+        Metadata::Platform,
+        Metadata::Notes,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metadata::Client => "client",
+            Metadata::Handling => "handling",
+            Metadata::Ruleset => "ruleset",
+            Metadata::Platform => "platform",
+            Metadata::Notes => "notes",
+        }
+    }
+
+    fn new(tag: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|key| *key as u8 == tag)
+    }
+}
+
+// Sets an entry, replacing any already there
+fn set_metadata(metadata: &mut Vec<(Metadata, String)>, key: Metadata, value: String) {
+    metadata.retain(|(other, _)| *other != key);
+    metadata.push((key, value));
+    metadata.sort_by_key(|(key, _)| *key as u8);
+}
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[wasm_bindgen]
 pub struct Recorder {
     seed: u64,
     kind: GameType,
+    rules: u64,
+    metadata: Vec<(Metadata, String)>,
     frames: Vec<RecorderFrame>,
     last_frame: u128,
 }
 
 impl Recorder {
-    fn create(seed: u64, game: &Game, now: u128) -> Self {
+    pub(crate) fn create(seed: u64, game: &Game, now: u128) -> Self {
         Self {
             seed,
             kind: game.kind(),
+            rules: game.rules().hash(),
+            metadata: vec![(Metadata::Ruleset, game.rules().to_string())],
             frames: vec![RecorderFrame::new(0, Input::default())],
             last_frame: now,
         }
     }
 
-    fn record_input(&mut self, input: Input, now: u128) {
-        const MAX_TIME: u128 = std::u16::MAX as u128;
+    pub(crate) fn record_input(&mut self, input: Input, now: u128) {
+        if input != Input::default() {
+            Self::push_frame(&mut self.frames, (now - self.last_frame) as u64, input);
+            self.last_frame = now;
+        }
+    }
 
-        let elapsed = now - self.last_frame;
+    // Edits take a marker frame followed by a frame holding the edit and the rest of the input
+    fn push_frame(frames: &mut Vec<RecorderFrame>, delta_time: u64, input: Input) {
+        match input.edit {
+            Some(edit) => {
+                let [kind, value] = edit.to_bytes();
+                let input = RecorderFrame::new(0, input).input;
+
+                frames.push(RecorderFrame {
+                    time: delta_time,
+                    input: RecorderFrame::EDIT,
+                });
+                frames.push(RecorderFrame::from([kind, value, input]));
+            }
+            None => frames.push(RecorderFrame::new(delta_time, input)),
+        }
+    }
 
-        if elapsed == MAX_TIME {
-            self.frames.push(RecorderFrame::new(std::u16::MAX, input));
-            self.last_frame = now;
-        } else if input != Input::default() {
-            self.frames.push(RecorderFrame::new(elapsed as u16, input));
-            self.last_frame = now;
+    // Frames at absolute times back into the times since the frame before
+    fn encode(frames: &VecDeque<Frame>) -> Vec<RecorderFrame> {
+        let mut encoded = Vec::with_capacity(frames.len());
+        let mut last = 0;
+
+        for frame in frames {
+            Self::push_frame(&mut encoded, (frame.time - last) as u64, frame.input);
+            last = frame.time;
         }
+
+        encoded
     }
 }
 
 #[wasm_bindgen]
 impl Recorder {
-    pub fn raw(self, username: &str, score: Score, duration: u64, end_time: i64) -> Vec<u8> {
-        let mut buffer = Vec::from(username.as_bytes());
-        buffer.push('\n' as u8);
+    pub fn set_metadata(&mut self, key: Metadata, value: String) {
+        set_metadata(&mut self.metadata, key, value)
+    }
+
+    pub fn raw(self, username: &str, game: &Game, duration: u64, end_time: i64) -> Vec<u8> {
+        let mut buffer = self.header(username, game, duration, end_time);
+
+        buffer.reserve(3 * self.frames.len() + 4);
+        Self::write_frames(&mut buffer, self.frames);
+        Self::write_checksum(&mut buffer);
+
+        buffer
+    }
+}
+
+impl Recorder {
+    fn header(&self, username: &str, game: &Game, duration: u64, end_time: i64) -> Vec<u8> {
+        let result = (game.score(), game.grade());
+        let mut buffer = Self::write_header(
+            username, self.kind, self.seed, self.rules, result, duration, end_time,
+        );
+
+        Self::write_metadata(&mut buffer, &self.metadata);
+
+        buffer
+    }
+
+    // The length of the whole section first, entries are cut to what their length fits
+    fn write_metadata(buffer: &mut Vec<u8>, metadata: &[(Metadata, String)]) {
+        let mut section = Vec::new();
+
+        for (key, value) in metadata {
+            let mut length = value.len().min(u16::MAX as usize);
+
+            while !value.is_char_boundary(length) {
+                length -= 1;
+            }
+
+            if section.len() + 3 + length > u16::MAX as usize {
+                break;
+            }
+
+            section.push(*key as u8);
+            section.extend_from_slice(&(length as u16).to_be_bytes());
+            section.extend_from_slice(&value.as_bytes()[..length]);
+        }
+
+        buffer.extend_from_slice(&(section.len() as u16).to_be_bytes());
+        buffer.extend_from_slice(&section);
+    }
+
+    fn write_header(
+        username: &str,
+        kind: GameType,
+        seed: u64,
+        rules: u64,
+        (score, grade): (Score, Grade),
+        duration: u64,
+        end_time: i64,
+    ) -> Vec<u8> {
+        // Cut to what the length fits, without splitting a character
+        let mut length = username.len().min(u16::MAX as usize);
+
+        while !username.is_char_boundary(length) {
+            length -= 1;
+        }
+
+        let mut buffer = Vec::from(MAGIC);
         buffer.push(VERSION);
-        buffer.push(self.kind.mode() as u8);
+        buffer.extend_from_slice(&(length as u16).to_be_bytes());
+        buffer.extend_from_slice(&username.as_bytes()[..length]);
+        buffer.push(kind.mode() as u8);
 
         let mut append = |num: u64| {
             for b in num.to_be_bytes() {
@@ -60,24 +241,117 @@ impl Recorder {
             }
         };
 
-        append(self.kind.lines());
+        append(kind.lines());
         append(score.score());
         append(score.lines());
         append(duration);
-        append(self.seed);
+        append(seed);
 
         for b in end_time.to_be_bytes() {
             buffer.push(b);
         }
 
-        buffer.reserve(32 + 3 * self.frames.len());
+        for b in kind.level().to_be_bytes() {
+            buffer.push(b);
+        }
 
-        for frame in self.frames {
-            buffer.push((frame.time >> 8) as u8);
-            buffer.push(frame.time as u8);
-            buffer.push(frame.input);
+        for b in score.level().to_be_bytes() {
+            buffer.push(b);
         }
 
+        buffer.push(grade as u8);
+        buffer.extend_from_slice(&rules.to_be_bytes());
+
+        buffer
+    }
+
+    fn write_checksum(buffer: &mut Vec<u8>) {
+        let crc = crc32(buffer);
+        buffer.extend_from_slice(&crc.to_be_bytes());
+    }
+
+    /*
+     * Each frame is its time since the frame before as a varint, shifted up by
+     * a bit that is set when the input is the same as the frame before and left
+     * out, an edit follows its marker frame as the three bytes it was recorded as
+     */
+    fn write_frames(buffer: &mut Vec<u8>, frames: Vec<RecorderFrame>) {
+        let mut frames = frames.into_iter();
+        let mut last_input = None;
+
+        while let Some(frame) = frames.next() {
+            let repeat = last_input == Some(frame.input);
+            let mut value = frame.time << 1 | repeat as u64;
+
+            while value >= 0x80 {
+                buffer.push(value as u8 | 0x80);
+                value >>= 7;
+            }
+
+            buffer.push(value as u8);
+
+            if !repeat {
+                buffer.push(frame.input);
+            }
+
+            last_input = Some(frame.input);
+
+            if frame.input == RecorderFrame::EDIT {
+                if let Some(edit) = frames.next() {
+                    buffer.extend_from_slice(&edit.to_bytes());
+                }
+
+                last_input = None;
+            }
+        }
+    }
+
+    // Frames as versions before 7 wrote them, three bytes each with the time in the first two
+    fn write_fixed_frames(buffer: &mut Vec<u8>, frames: Vec<RecorderFrame>) -> usize {
+        let keep_alive = RecorderFrame::new(u16::MAX as u64, Input::default());
+        let mut frames = frames.into_iter();
+        let mut written = 0;
+
+        while let Some(mut frame) = frames.next() {
+            // Gaps too long for two bytes are bridged with frames that press nothing
+            while frame.time > u16::MAX as u64 {
+                buffer.extend_from_slice(&keep_alive.to_bytes());
+                frame.time -= u16::MAX as u64;
+                written += 1;
+            }
+
+            buffer.extend_from_slice(&frame.to_bytes());
+            written += 1;
+
+            if frame.input == RecorderFrame::EDIT {
+                if let Some(edit) = frames.next() {
+                    buffer.extend_from_slice(&edit.to_bytes());
+                }
+            }
+        }
+
+        written
+    }
+
+    // Both players of a versus match, the header holds the first player's result
+    // and the number of frames of the first player comes before the frames of both
+    pub fn raw_versus(
+        self,
+        opponent: Recorder,
+        username: &str,
+        versus: &Match,
+        duration: u64,
+        end_time: i64,
+    ) -> Vec<u8> {
+        let mut buffer = self.header(username, versus.player(0), duration, end_time);
+
+        buffer.extend_from_slice(&(self.frames.len() as u64).to_be_bytes());
+        buffer.reserve(3 * (self.frames.len() + opponent.frames.len()) + 4);
+
+        Self::write_frames(&mut buffer, self.frames);
+        Self::write_frames(&mut buffer, opponent.frames);
+        Self::write_checksum(&mut buffer);
+
         buffer
     }
 }
@@ -106,8 +380,8 @@ impl Recorder {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-struct RecorderFrame {
-    pub time: u16,
+pub(crate) struct RecorderFrame {
+    pub time: u64,
     pub input: u8,
 }
 
@@ -121,7 +395,10 @@ impl RecorderFrame {
     const SNAP_LEFT: u8 = Self::LEFT | (1 << 2);
     const SNAP_RIGHT: u8 = Self::RIGHT | (1 << 2);
 
-    const fn new(delta_time: u16, input: Input) -> Self {
+    // A direction no input can have, marks that the next frame holds a sandbox edit
+    const EDIT: u8 = 0b011;
+
+    pub(crate) const fn new(delta_time: u64, input: Input) -> Self {
         let input = if !input.quit {
             (input.hold as u8) << 7
                 | (input.hard_drop as u8) << 6
@@ -149,25 +426,31 @@ impl RecorderFrame {
         }
     }
 
-    const fn input(&self) -> Input {
+    pub(crate) const fn input(&self) -> Input {
+        let quit = self.input == std::u8::MAX;
+
+        // Quitting sets every bit, none of which were pressed along with it
+        let input = if quit { 0 } else { self.input };
+
         Input {
-            hold: self.input & 1 << 7 != 0,
-            quit: self.input == std::u8::MAX,
-            hard_drop: self.input & 1 << 6 != 0,
-            soft_drop: self.input & 1 << 5 != 0,
-            rotation: match self.input & (0b11 << 3) {
+            hold: input & 1 << 7 != 0,
+            quit,
+            hard_drop: input & 1 << 6 != 0,
+            soft_drop: input & 1 << 5 != 0,
+            rotation: match input & (0b11 << 3) {
                 Self::QUARTER => InputRotation::Quarter,
                 Self::TWO_QUARTER => InputRotation::TwoQuarter,
                 Self::THREE_QUARTER => InputRotation::ThreeQuarter,
                 _ => InputRotation::None,
             },
-            direction: match self.input & 0b111 {
+            direction: match input & 0b111 {
                 Self::LEFT => InputDirection::Left,
                 Self::RIGHT => InputDirection::Right,
                 Self::SNAP_LEFT => InputDirection::SnapLeft,
                 Self::SNAP_RIGHT => InputDirection::SnapRight,
                 _ => InputDirection::None,
             },
+            edit: None,
         }
     }
 }
@@ -175,129 +458,339 @@ impl RecorderFrame {
 impl From<[u8; 3]> for RecorderFrame {
     fn from(raw: [u8; 3]) -> Self {
         Self {
-            time: (raw[0] as u16) << 8 | raw[1] as u16,
+            time: (raw[0] as u64) << 8 | raw[1] as u64,
             input: raw[2],
         }
     }
 }
 
-#[derive(Clone, Debug)]
+impl RecorderFrame {
+    // The three bytes versions before 7 wrote, the time has to fit in two of them
+    fn to_bytes(self) -> [u8; 3] {
+        [(self.time >> 8) as u8, self.time as u8, self.input]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ReplayError {
-    UsernameNotFound,
-    BufferTooShort,
+    BadMagic,
+    UnsupportedVersion(u8),
+    InvalidMode(u8),
+    InvalidUsername,
+    ChecksumMismatch,
+    Truncated,
+    // A field holds a value no version writes
+    Corrupted,
 }
 
 impl std::fmt::Display for ReplayError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                ReplayError::UsernameNotFound => "No username in replay",
-                ReplayError::BufferTooShort => "Buffer too short, replay file likely corrupted",
+        match self {
+            ReplayError::BadMagic => write!(f, "Not a replay file"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "Replay version {} is not supported", version)
             }
-        )
+            ReplayError::InvalidMode(mode) => write!(f, "Unknown game mode {} in replay", mode),
+            ReplayError::InvalidUsername => write!(f, "Username in replay is not valid UTF-8"),
+            ReplayError::ChecksumMismatch => write!(f, "Checksum does not match, replay corrupted"),
+            ReplayError::Truncated => write!(f, "Replay ends early, file likely truncated"),
+            ReplayError::Corrupted => write!(f, "Replay holds invalid data, file likely corrupted"),
+        }
+    }
+}
+
+// Reads from the front of a buffer, running out of bytes means the replay was cut short
+struct Reader<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], ReplayError> {
+        if length > self.buffer.len() {
+            return Err(ReplayError::Truncated);
+        }
+
+        let (taken, rest) = self.buffer.split_at(length);
+        self.buffer = rest;
+
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ReplayError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, ReplayError> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> Result<i64, ReplayError> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    // Seven bits at a time from the lowest, the top bit of each byte set while more follow
+    fn varint(&mut self) -> Result<u64, ReplayError> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+
+            if shift == 63 && byte > 1 {
+                return Err(ReplayError::Corrupted);
+            }
+
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(ReplayError::Corrupted)
     }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Replay {
-    username: String,
-    version: u8,
-    score: Score,
-    duration: u64,
-    time_stamp: i64,
-    seed: u64,
-    kind: GameType,
-    frames: VecDeque<Frame>,
+    pub(crate) username: String,
+    pub(crate) version: u8,
+    pub(crate) score: Score,
+    pub(crate) grade: Grade,
+    pub(crate) duration: u64,
+    pub(crate) time_stamp: i64,
+    pub(crate) seed: u64,
+    pub(crate) kind: GameType,
+    pub(crate) rules: u64,
+    pub(crate) metadata: Vec<(Metadata, String)>,
+    pub(crate) frames: VecDeque<Frame>,
+    pub(crate) opponent: VecDeque<Frame>,
 }
 
 impl Replay {
     pub fn new(buffer: Vec<u8>) -> Result<Self, ReplayError> {
-        let mut buf = BufReader::new(buffer.as_slice());
+        if !buffer.starts_with(&MAGIC) {
+            return Self::legacy(&buffer);
+        }
 
-        let mut username = String::default();
-        match buf.read_line(&mut username) {
-            Ok(_) => {}
-            Err(_) => return Err(ReplayError::UsernameNotFound),
+        let mut reader = Reader {
+            buffer: &buffer[MAGIC.len()..],
         };
-        let username = username.trim().to_string();
 
-        let mut version = [0u8];
-        match buf.read_exact(&mut version) {
-            Ok(_) => {}
-            Err(_) => return Err(ReplayError::BufferTooShort),
+        let version = reader.byte()?;
+
+        if !(LEGACY_VERSION + 1..=VERSION).contains(&version) {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+
+        // Everything up to the checksum in the last 4 bytes is covered by it
+        let payload = match buffer.len().checked_sub(4) {
+            Some(length) if length >= MAGIC.len() + 3 => &buffer[..length],
+            _ => return Err(ReplayError::Truncated),
         };
-        let version = version[0];
 
-        let mut mode = [0u8];
-        let mode = match buf.read_exact(&mut mode) {
-            Ok(_) => match GameMode::new(mode[0]) {
-                Ok(mode) => mode,
-                Err(_) => return Err(ReplayError::BufferTooShort),
-            },
-            Err(_) => return Err(ReplayError::BufferTooShort),
+        if crc32(payload).to_be_bytes() != buffer[payload.len()..] {
+            return Err(ReplayError::ChecksumMismatch);
+        }
+
+        let mut reader = Reader {
+            buffer: &payload[MAGIC.len() + 1..],
+        };
+
+        let length = reader.u16()? as usize;
+        let username = match std::str::from_utf8(reader.take(length)?) {
+            Ok(username) => username.to_string(),
+            Err(_) => return Err(ReplayError::InvalidUsername),
         };
 
-        let mut num = [0u8; 8];
+        Self::read(reader, username, version)
+    }
 
-        match buf.read_exact(&mut num) {
-            Ok(_) => {}
-            Err(_) => return Err(ReplayError::BufferTooShort),
+    // Versions 1 to 4 start with the username up to a newline and have no checksum
+    fn legacy(buffer: &[u8]) -> Result<Self, ReplayError> {
+        let newline = match buffer.iter().position(|byte| *byte == b'\n') {
+            Some(newline) => newline,
+            None => return Err(ReplayError::BadMagic),
         };
-        let count = u64::from_be_bytes(num);
 
-        let kind = GameType::new(mode, count);
+        let mut reader = Reader {
+            buffer: &buffer[newline + 1..],
+        };
 
-        match buf.read_exact(&mut num) {
-            Ok(_) => {}
-            Err(_) => return Err(ReplayError::BufferTooShort),
+        let version = match reader.byte() {
+            Ok(version @ 1..=LEGACY_VERSION) => version,
+            _ => return Err(ReplayError::BadMagic),
         };
-        let score = u64::from_be_bytes(num);
 
-        match buf.read_exact(&mut num) {
-            Ok(_) => {}
-            Err(_) => return Err(ReplayError::BufferTooShort),
+        let username = match std::str::from_utf8(&buffer[..newline]) {
+            Ok(username) => username.trim().to_string(),
+            Err(_) => return Err(ReplayError::InvalidUsername),
         };
-        let lines = u64::from_be_bytes(num);
 
-        let score = Score { score, lines };
+        Self::read(reader, username, version)
+    }
 
-        match buf.read_exact(&mut num) {
-            Ok(_) => {}
-            Err(_) => return Err(ReplayError::BufferTooShort),
+    // Everything after the username, the same for every version save for the fields added over time
+    fn read(mut reader: Reader, username: String, version: u8) -> Result<Self, ReplayError> {
+        let mode = reader.byte()?;
+        let mode = match GameMode::new(mode) {
+            Ok(mode) => mode,
+            Err(_) => return Err(ReplayError::InvalidMode(mode)),
         };
-        let duration = u64::from_be_bytes(num);
 
-        match buf.read_exact(&mut num) {
-            Ok(_) => {}
-            Err(_) => return Err(ReplayError::BufferTooShort),
+        let count = reader.u64()?;
+        let score = reader.u64()?;
+        let lines = reader.u64()?;
+        let duration = reader.u64()?;
+        let seed = reader.u64()?;
+        let time_stamp = reader.i64()?;
+
+        // Version 1 had no levels, and wrote line races as marathons with a goal
+        let (kind, score) = if version < 2 {
+            let mode = match mode {
+                GameMode::Marathon if count > 0 => GameMode::Lines,
+                mode => mode,
+            };
+
+            (
+                GameType::new(mode, count),
+                Score {
+                    score,
+                    lines,
+                    level: 1,
+                },
+            )
+        } else {
+            // Version 2 and later store the starting and the final level
+            let start_level = reader.u64()?;
+            let level = reader.u64()?;
+
+            (
+                GameType::new(mode, count).with_level(start_level),
+                Score {
+                    score,
+                    lines,
+                    level,
+                },
+            )
         };
-        let seed = u64::from_be_bytes(num);
 
-        match buf.read_exact(&mut num) {
-            Ok(_) => {}
-            Err(_) => return Err(ReplayError::BufferTooShort),
+        let grade = if version < 3 {
+            Grade::Nine
+        } else {
+            match Grade::new(reader.byte()?) {
+                Ok(grade) => grade,
+                Err(_) => return Err(ReplayError::Corrupted),
+            }
         };
-        let time_stamp = i64::from_be_bytes(num);
 
-        let mut time = 0u128;
+        // Replays from before rulesets were all played by the guideline
+        let rules = if version < 4 {
+            Ruleset::guideline().hash()
+        } else {
+            reader.u64()?
+        };
 
-        let mut frames = VecDeque::with_capacity((buffer.len() - 32) / 3);
+        let mut metadata = Vec::new();
+
+        if version >= METADATA_VERSION {
+            let length = reader.u16()? as usize;
+            let mut section = Reader {
+                buffer: reader.take(length)?,
+            };
+
+            while !section.buffer.is_empty() {
+                let tag = section.byte()?;
+                let length = section.u16()? as usize;
+                let value = match std::str::from_utf8(section.take(length)?) {
+                    Ok(value) => value.to_string(),
+                    Err(_) => return Err(ReplayError::Corrupted),
+                };
+
+                // Tags from newer versions are left out
+                if let Some(key) = Metadata::new(tag) {
+                    set_metadata(&mut metadata, key, value);
+                }
+            }
+        }
+
+        let split = if mode == GameMode::Versus {
+            Some(reader.u64()? as usize)
+        } else {
+            None
+        };
+
+        let compact = version >= COMPACT_VERSION;
+
+        if !compact && !reader.buffer.len().is_multiple_of(3) {
+            return Err(ReplayError::Truncated);
+        }
+
+        let mut time = 0u128;
+        let mut frames = VecDeque::with_capacity(reader.buffer.len() / 2);
+        let mut last_input = None;
+
+        while !reader.buffer.is_empty() {
+            let frame = if compact {
+                let value = reader.varint()?;
+                let input = match value & 1 {
+                    0 => reader.byte()?,
+                    _ => last_input.ok_or(ReplayError::Corrupted)?,
+                };
+
+                RecorderFrame {
+                    time: value >> 1,
+                    input,
+                }
+            } else {
+                RecorderFrame::from(<[u8; 3]>::try_from(reader.take(3)?).unwrap())
+            };
 
-        let mut frame_data = [0u8; 3];
-        while let Ok(_) = buf.read_exact(&mut frame_data) {
-            let frame = RecorderFrame::from(frame_data);
             time += frame.time as u128;
+            last_input = Some(frame.input);
+
+            if frame.input == RecorderFrame::EDIT {
+                last_input = None;
 
-            frames.push_back(Frame::new(time, frame));
+                let frame_data = reader.take(3)?;
+
+                let edit = match Edit::from_bytes([frame_data[0], frame_data[1]]) {
+                    Ok(edit) => edit,
+                    Err(_) => return Err(ReplayError::Corrupted),
+                };
+
+                let mut frame = Frame::new(time, RecorderFrame::from([0, 0, frame_data[2]]));
+                frame.input.edit = Some(edit);
+
+                frames.push_back(frame);
+            } else {
+                frames.push_back(Frame::new(time, frame));
+            }
+
+            // The second player's times start over from the beginning of the match
+            if Some(frames.len()) == split {
+                time = 0;
+            }
         }
 
+        let opponent = match split {
+            Some(split) if split <= frames.len() => frames.split_off(split),
+            Some(_) => return Err(ReplayError::Truncated),
+            None => VecDeque::new(),
+        };
+
         Ok(Self {
             seed,
             kind,
+            rules,
+            metadata,
             score,
+            grade,
             frames,
+            opponent,
             version,
             username,
             duration,
@@ -309,14 +802,27 @@ impl Replay {
         self.seed
     }
 
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
     pub fn next(&mut self) -> Option<Frame> {
         self.frames.pop_front()
     }
 
+    // Frames of the second player in a versus replay
+    pub fn next_opponent(&mut self) -> Option<Frame> {
+        self.opponent.pop_front()
+    }
+
     pub fn score(&self) -> Score {
         self.score
     }
 
+    pub fn grade(&self) -> Grade {
+        self.grade
+    }
+
     pub fn username(&self) -> &String {
         &self.username
     }
@@ -332,6 +838,90 @@ impl Replay {
     pub fn kind(&self) -> GameType {
         self.kind
     }
+
+    // Hash of the ruleset the replay was recorded with
+    pub fn rules(&self) -> u64 {
+        self.rules
+    }
+
+    // Whether the replay can be played back by these rules
+    pub fn matches(&self, rules: &Ruleset) -> bool {
+        self.rules == rules.hash()
+    }
+
+    pub fn metadata(&self, key: Metadata) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(other, _)| *other == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Every entry the replay has, in the order of their tags
+    pub fn metadata_entries(&self) -> &[(Metadata, String)] {
+        &self.metadata
+    }
+
+    pub fn set_metadata(&mut self, key: Metadata, value: String) {
+        set_metadata(&mut self.metadata, key, value)
+    }
+
+    // The rules the replay was recorded by, from its metadata if they are there and match the hash
+    pub fn ruleset(&self) -> Option<Ruleset> {
+        self.metadata(Metadata::Ruleset)
+            .and_then(|rules| rules.parse::<Ruleset>().ok())
+            .filter(|rules| self.matches(rules))
+    }
+
+    /*
+     * The replay in the current format, for replays read from an older version
+     * or built by hand. Games from before every millisecond was ticked cannot
+     * be verified, so they are written as version 5 to stay that way, which
+     * leaves out their metadata
+     */
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Recorder::write_header(
+            &self.username,
+            self.kind,
+            self.seed,
+            self.rules,
+            (self.score, self.grade),
+            self.duration,
+            self.time_stamp,
+        );
+
+        let frames = Recorder::encode(&self.frames);
+        let opponent = Recorder::encode(&self.opponent);
+        let versus = self.kind.mode() == GameMode::Versus;
+
+        if self.version < VERIFIABLE_VERSION {
+            buffer[MAGIC.len()] = CHECKSUM_VERSION;
+
+            // The split counts the frames written, which the gaps bridged add to
+            let mut written = Vec::new();
+            let split = Recorder::write_fixed_frames(&mut written, frames);
+
+            if versus {
+                buffer.extend_from_slice(&(split as u64).to_be_bytes());
+                Recorder::write_fixed_frames(&mut written, opponent);
+            }
+
+            buffer.extend_from_slice(&written);
+        } else {
+            Recorder::write_metadata(&mut buffer, &self.metadata);
+
+            if versus {
+                buffer.extend_from_slice(&(frames.len() as u64).to_be_bytes());
+                Recorder::write_frames(&mut buffer, frames);
+                Recorder::write_frames(&mut buffer, opponent);
+            } else {
+                Recorder::write_frames(&mut buffer, frames);
+            }
+        }
+
+        Recorder::write_checksum(&mut buffer);
+
+        buffer
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -0,0 +1,307 @@
+use std::{fmt::Display, str::FromStr};
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::score::ScoreTable;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[wasm_bindgen]
+#[repr(u8)]
+pub enum Kicks {
+    // Super rotation system, with the 180 kicks
+    Srs,
+    // One column to either side when the rotation is blocked
+    Basic,
+    None,
+}
+
+impl Display for Kicks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Kicks::Srs => write!(f, "srs"),
+            Kicks::Basic => write!(f, "basic"),
+            Kicks::None => write!(f, "none"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RulesetError {
+    UnknownPreset,
+    InvalidOption,
+    InvalidValue,
+}
+
+impl Display for RulesetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                RulesetError::UnknownPreset => "Preset must be one of guideline, classic or tgm",
+                RulesetError::InvalidOption => "Unknown option in ruleset",
+                RulesetError::InvalidValue => "Invalid value in ruleset",
+            }
+        )
+    }
+}
+
+/*
+ * A ruleset is written as options, any option left out keeps the value of the
+ * preset, which should come first
+ *
+ * preset = guideline
+ * name = Slow
+ * gravity = 1000
+ * lock_delay = 1000
+ * kicks = basic
+ * clear_scores = 0 100 300 500 800
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[wasm_bindgen]
+pub struct Ruleset {
+    name: String,
+    spawn_column: i8,
+    gravity: u128,
+    lock_delay: u128,
+    entry_delay: u128,
+    clear_delay: u128,
+    kicks: Kicks,
+    rotate_180: bool,
+    hold: bool,
+    scores: ScoreTable,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self::guideline()
+    }
+}
+
+impl Ruleset {
+    pub const PRESETS: [&'static str; 3] = ["guideline", "classic", "tgm"];
+
+    pub fn preset(name: &str) -> Result<Self, RulesetError> {
+        match name.to_ascii_lowercase().as_str() {
+            "guideline" => Ok(Self::guideline()),
+            "classic" => Ok(Self::classic()),
+            "tgm" => Ok(Self::tgm()),
+            _ => Err(RulesetError::UnknownPreset),
+        }
+    }
+
+    pub(crate) fn scores(&self) -> &ScoreTable {
+        &self.scores
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        let mut buffer = vec![self.spawn_column as u8];
+
+        for delay in [
+            self.gravity,
+            self.lock_delay,
+            self.entry_delay,
+            self.clear_delay,
+        ] {
+            buffer.extend_from_slice(&delay.to_be_bytes());
+        }
+
+        buffer.extend_from_slice(&[self.kicks as u8, self.rotate_180 as u8, self.hold as u8]);
+
+        for score in self.scores.iter() {
+            buffer.extend_from_slice(&score.to_be_bytes());
+        }
+
+        buffer
+    }
+}
+
+#[wasm_bindgen]
+impl Ruleset {
+    pub fn guideline() -> Self {
+        Self {
+            name: String::from("Guideline"),
+            spawn_column: 3,
+            gravity: 500,
+            lock_delay: 1500,
+            entry_delay: 0,
+            clear_delay: 0,
+            kicks: Kicks::Srs,
+            rotate_180: true,
+            hold: true,
+            scores: ScoreTable::GUIDELINE,
+        }
+    }
+
+    // Slow, no kicks, no hold and the original scoring
+    pub fn classic() -> Self {
+        Self {
+            name: String::from("Classic"),
+            gravity: 800,
+            lock_delay: 50,
+            entry_delay: 167,
+            clear_delay: 333,
+            kicks: Kicks::None,
+            rotate_180: false,
+            hold: false,
+            scores: ScoreTable {
+                clears: [0, 40, 100, 300, 1200],
+                t_spins: [0, 40, 100, 300],
+                spins: [0, 40, 100, 300],
+            },
+            ..Self::guideline()
+        }
+    }
+
+    // Short lock delay with entry and clear delays, kicks only to the side
+    pub fn tgm() -> Self {
+        Self {
+            name: String::from("TGM"),
+            gravity: 1067,
+            lock_delay: 500,
+            entry_delay: 417,
+            clear_delay: 667,
+            kicks: Kicks::Basic,
+            rotate_180: false,
+            hold: false,
+            ..Self::guideline()
+        }
+    }
+
+    pub fn from_string(rules: String) -> Option<Ruleset> {
+        rules.parse().ok()
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn spawn_column(&self) -> i8 {
+        self.spawn_column
+    }
+
+    // Milliseconds between each row the piece falls, for modes without their own speed curve
+    pub fn gravity(&self) -> u64 {
+        self.gravity as u64
+    }
+
+    pub fn lock_delay(&self) -> u64 {
+        self.lock_delay as u64
+    }
+
+    pub fn entry_delay(&self) -> u64 {
+        self.entry_delay as u64
+    }
+
+    pub fn clear_delay(&self) -> u64 {
+        self.clear_delay as u64
+    }
+
+    pub fn kicks(&self) -> Kicks {
+        self.kicks
+    }
+
+    pub fn rotate_180(&self) -> bool {
+        self.rotate_180
+    }
+
+    pub fn hold(&self) -> bool {
+        self.hold
+    }
+
+    // FNV-1a of every rule but the name, stored in replays to play them back by the same rules
+    pub fn hash(&self) -> u64 {
+        self.bytes().iter().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+}
+
+impl Display for Ruleset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |scores: &[u64]| {
+            scores
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        writeln!(f, "name = {}", self.name)?;
+        writeln!(f, "spawn_column = {}", self.spawn_column)?;
+        writeln!(f, "gravity = {}", self.gravity)?;
+        writeln!(f, "lock_delay = {}", self.lock_delay)?;
+        writeln!(f, "entry_delay = {}", self.entry_delay)?;
+        writeln!(f, "clear_delay = {}", self.clear_delay)?;
+        writeln!(f, "kicks = {}", self.kicks)?;
+        writeln!(f, "rotate_180 = {}", self.rotate_180)?;
+        writeln!(f, "hold = {}", self.hold)?;
+        writeln!(f, "clear_scores = {}", list(&self.scores.clears))?;
+        writeln!(f, "t_spin_scores = {}", list(&self.scores.t_spins))?;
+        writeln!(f, "spin_scores = {}", list(&self.scores.spins))
+    }
+}
+
+impl FromStr for Ruleset {
+    type Err = RulesetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn number<T: FromStr>(value: &str) -> Result<T, RulesetError> {
+            value.parse().map_err(|_| RulesetError::InvalidValue)
+        }
+
+        fn scores<const N: usize>(value: &str) -> Result<[u64; N], RulesetError> {
+            let scores = value
+                .split_whitespace()
+                .map(number)
+                .collect::<Result<Vec<u64>, _>>()?;
+
+            scores.try_into().map_err(|_| RulesetError::InvalidValue)
+        }
+
+        let mut rules = Self {
+            name: String::from("Custom"),
+            ..Self::guideline()
+        };
+
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (option, value) = match line.split_once('=') {
+                Some((option, value)) => (option.trim(), value.trim()),
+                None => return Err(RulesetError::InvalidOption),
+            };
+
+            match option {
+                "preset" => rules = Self::preset(value)?,
+                "name" => rules.name = value.to_string(),
+                "spawn_column" => match number(value)? {
+                    column @ 0..=6 => rules.spawn_column = column,
+                    _ => return Err(RulesetError::InvalidValue),
+                },
+                "gravity" => rules.gravity = number::<u128>(value)?.max(1),
+                "lock_delay" => rules.lock_delay = number(value)?,
+                "entry_delay" => rules.entry_delay = number(value)?,
+                "clear_delay" => rules.clear_delay = number(value)?,
+                "kicks" => {
+                    rules.kicks = match value.to_ascii_lowercase().as_str() {
+                        "srs" => Kicks::Srs,
+                        "basic" => Kicks::Basic,
+                        "none" => Kicks::None,
+                        _ => return Err(RulesetError::InvalidValue),
+                    }
+                }
+                "rotate_180" => rules.rotate_180 = number(value)?,
+                "hold" => rules.hold = number(value)?,
+                "clear_scores" => rules.scores.clears = scores(value)?,
+                "t_spin_scores" => rules.scores.t_spins = scores(value)?,
+                "spin_scores" => rules.scores.spins = scores(value)?,
+                _ => return Err(RulesetError::InvalidOption),
+            }
+        }
+
+        Ok(rules)
+    }
+}
@@ -0,0 +1,43 @@
+use crate::piece::PieceType;
+
+// Changes to the board only allowed in sandbox mode, recorded alongside the input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edit {
+    // Replaces the active piece
+    Piece(PieceType),
+    // Swaps the active and the held piece, ignoring the once per piece limit
+    SwapHold,
+    // Fills or empties a cell, y counts rows from the top of the whole board
+    Toggle(usize, usize),
+    Garbage,
+}
+
+impl Edit {
+    const PIECE: u8 = 0;
+    const SWAP_HOLD: u8 = 1;
+    const TOGGLE: u8 = 2;
+    const GARBAGE: u8 = 3;
+
+    // The kind in the low nibble with the column above it, followed by the row or piece
+    pub(crate) fn to_bytes(self) -> [u8; 2] {
+        match self {
+            Edit::Piece(kind) => [Self::PIECE, kind as u8],
+            Edit::SwapHold => [Self::SWAP_HOLD, 0],
+            Edit::Toggle(x, y) => [Self::TOGGLE | (x as u8) << 4, y as u8],
+            Edit::Garbage => [Self::GARBAGE, 0],
+        }
+    }
+
+    pub(crate) fn from_bytes(bytes: [u8; 2]) -> Result<Self, ()> {
+        match bytes[0] & 0xf {
+            Self::PIECE => match PieceType::ALL.get(bytes[1] as usize) {
+                Some(kind) => Ok(Edit::Piece(*kind)),
+                None => Err(()),
+            },
+            Self::SWAP_HOLD => Ok(Edit::SwapHold),
+            Self::TOGGLE => Ok(Edit::Toggle((bytes[0] >> 4) as usize, bytes[1] as usize)),
+            Self::GARBAGE => Ok(Edit::Garbage),
+            _ => Err(()),
+        }
+    }
+}
@@ -5,17 +5,49 @@ use crate::{
     piece::PieceType,
 };
 
-#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+// Points for each clear by lines cleared, multiplied by the level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ScoreTable {
+    pub clears: [u64; 5],
+    pub t_spins: [u64; 4],
+    // Spins of pieces other than T
+    pub spins: [u64; 4],
+}
+
+impl ScoreTable {
+    pub const GUIDELINE: Self = Self {
+        clears: [0, 100, 300, 500, 800],
+        t_spins: [100, 800, 1200, 1600],
+        spins: [0, 100, 300, 500],
+    };
+
+    pub fn iter(&self) -> impl Iterator<Item = &u64> {
+        self.clears.iter().chain(&self.t_spins).chain(&self.spins)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[wasm_bindgen]
 pub struct Score {
     pub(crate) score: u64,
     pub(crate) lines: u64,
+    pub(crate) level: u64,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[wasm_bindgen]
 impl Score {
     pub fn new() -> Self {
-        Self { score: 0, lines: 0 }
+        Self {
+            score: 0,
+            lines: 0,
+            level: 1,
+        }
     }
 
     pub fn score(&self) -> u64 {
@@ -26,39 +58,36 @@ impl Score {
         self.lines
     }
 
+    pub fn level(&self) -> u64 {
+        self.level
+    }
+
     pub fn update(&mut self, tick: TickResult) {
-        let (lines, score) = match tick.kind() {
-            TickType::None => (0, 0),
-            TickType::Clear => match tick.lines() {
-                1 => (1, 100),
-                2 => (2, 300),
-                3 => (3, 500),
-                4 => (4, 800),
+        self.update_with(tick, &ScoreTable::GUIDELINE)
+    }
+}
+
+impl Score {
+    pub(crate) fn update_with(&mut self, tick: TickResult, table: &ScoreTable) {
+        let lines = tick.lines() as usize;
+
+        let score = match tick.kind() {
+            TickType::None => 0,
+            TickType::Clear => match table.clears.get(lines) {
+                Some(score) if lines > 0 => *score,
                 _ => unreachable!("Only clears of 1-4 lines are possible"),
             },
 
-            TickType::Spin => match tick.piece() {
-                PieceType::T => match tick.lines() {
-                    0 => (0, 100),
-                    1 => (1, 800),
-                    2 => (2, 1200),
-                    3 => (3, 1600),
-                    _ => unreachable!("Only spins of 0-3 lines are possible"),
-                },
-
-                _ => match tick.lines() {
-                    0 => (0, 0),
-                    1 => (1, 100),
-                    2 => (2, 300),
-                    3 => (3, 500),
-                    _ => unreachable!("Only spins of 0-3 lines are possible"),
-                },
+            TickType::Spin => match (tick.piece(), lines) {
+                (_, 4..) => unreachable!("Only spins of 0-3 lines are possible"),
+                (PieceType::T, _) => table.t_spins[lines],
+                _ => table.spins[lines],
             },
 
             TickType::GameOver => unreachable!("Game should terminate before this"),
         };
 
-        self.score += score;
-        self.lines += lines;
+        self.score += score * self.level;
+        self.lines += lines as u64;
     }
 }
@@ -0,0 +1,104 @@
+use std::ops::Range;
+
+use crate::{Bag, Frame, Game, Input, Replay, Ruleset, TickResult, TickType};
+
+// Where a simulation is, kept by playback to go back to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct State {
+    tick: u128,
+    next: usize,
+    game: Game,
+    over: bool,
+}
+
+/*
+ * Plays a replay's frames into its game one millisecond at a time. Every
+ * tick is stepped and every frame up to it is taken in order, so the game
+ * only depends on the frames and never on how often it is advanced
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Simulator {
+    frames: Vec<Frame>,
+    state: State,
+    taken: Range<usize>,
+}
+
+impl Simulator {
+    pub fn new(mut replay: Replay, rules: &Ruleset) -> Self {
+        let game = Game::new(Bag::new(replay.seed()), replay.kind(), rules);
+
+        Self {
+            frames: std::iter::from_fn(|| replay.next()).collect(),
+            state: State {
+                tick: 0,
+                next: 0,
+                game,
+                over: false,
+            },
+            taken: 0..0,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.state.game
+    }
+
+    pub fn tick(&self) -> u128 {
+        self.state.tick
+    }
+
+    pub fn over(&self) -> bool {
+        self.state.over
+    }
+
+    // Frames taken by the last step, more than one when they share a tick
+    pub fn taken(&self) -> &[Frame] {
+        &self.frames[self.taken.clone()]
+    }
+
+    // Tick of the last recorded input
+    pub(crate) fn last_input(&self) -> u128 {
+        self.frames.last().map_or(0, |frame| frame.time)
+    }
+
+    // Plays one millisecond with every frame up to it merged, doing nothing once the game is over
+    pub fn step(&mut self) -> Option<TickResult> {
+        let state = &mut self.state;
+
+        if state.over {
+            return None;
+        }
+
+        state.tick += 1;
+
+        let start = state.next;
+        let mut input = Input::default();
+
+        // The first frame is recorded at 0, before the first tick
+        while let Some(frame) = self.frames.get(state.next).filter(|f| f.time <= state.tick) {
+            input = input.merge(frame.input);
+            state.next += 1;
+        }
+
+        self.taken = start..state.next;
+
+        let res = state.game.step(input, state.tick);
+        state.over = res.kind() == TickType::GameOver;
+
+        Some(res)
+    }
+
+    // Steps up to the tick or the end of the game, however far away it is
+    pub fn run_to(&mut self, tick: u128) {
+        while self.tick() < tick && self.step().is_some() {}
+    }
+
+    pub(crate) fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub(crate) fn restore(&mut self, state: State) {
+        self.state = state;
+        self.taken = 0..0;
+    }
+}
@@ -0,0 +1,51 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::piece::PieceType;
+
+const PIECE_TYPES: usize = PieceType::ALL.len();
+
+// Distribution of the pieces dealt during a game, gaps are counted in pieces
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[wasm_bindgen]
+pub struct PieceStats {
+    counts: [u64; PIECE_TYPES],
+    gaps: [u64; PIECE_TYPES],
+    drought: u64,
+}
+
+impl PieceStats {
+    pub(crate) fn record(&mut self, kind: PieceType) {
+        for gap in self.gaps.iter_mut() {
+            *gap += 1;
+        }
+
+        self.counts[kind as usize] += 1;
+        self.gaps[kind as usize] = 0;
+        self.drought = self.drought.max(self.gaps[PieceType::I as usize]);
+    }
+}
+
+#[wasm_bindgen]
+impl PieceStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn count(&self, kind: PieceType) -> u64 {
+        self.counts[kind as usize]
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    // Pieces dealt since the last piece of this kind
+    pub fn gap(&self, kind: PieceType) -> u64 {
+        self.gaps[kind as usize]
+    }
+
+    // Longest stretch of pieces without an I piece, including the current one
+    pub fn drought(&self) -> u64 {
+        self.drought
+    }
+}
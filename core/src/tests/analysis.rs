@@ -0,0 +1,39 @@
+#[allow(unused_imports)]
+use crate::{tests::util::recorded_game, PieceType, Replay, Ruleset};
+
+#[test]
+fn pieces_are_timed() {
+    let (game, recorder, end) = recorded_game();
+    let replay = Replay::new(recorder.raw("player", &game, end, 0)).unwrap();
+    let analysis = replay.analyse(&Ruleset::guideline());
+
+    // Every 100ms an I piece is hard dropped at 8ms, after edits that are not keys
+    let pieces = analysis.pieces();
+
+    assert_eq!(pieces.len(), 15);
+    assert_eq!(
+        (pieces[0].start, pieces[0].end, pieces[0].time()),
+        (0, 8, 8)
+    );
+    assert_eq!(
+        (pieces[1].start, pieces[1].end, pieces[1].time()),
+        (8, 108, 100)
+    );
+    assert!(pieces[1..].iter().all(|piece| piece.piece == PieceType::I));
+    assert!(pieces
+        .iter()
+        .all(|piece| (piece.keys, piece.lines) == (1, 1)));
+
+    assert_eq!(analysis.duration(), 1500);
+    assert_eq!((analysis.keys(), analysis.lines()), (15, 15));
+    assert_eq!(analysis.attack(), game.attack().sent());
+    assert_eq!(
+        analysis.attack(),
+        pieces.iter().map(|piece| piece.attack).sum::<u64>()
+    );
+
+    assert_eq!(analysis.pps(), 10.0);
+    assert_eq!(analysis.kpp(), 1.0);
+    assert_eq!(analysis.lpm(), 600.0);
+    assert_eq!(analysis.slowest(2), [1, 2]);
+}
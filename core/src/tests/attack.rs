@@ -0,0 +1,82 @@
+#[allow(unused_imports)]
+use crate::{
+    piece::PieceType, tests::util::NONE, Attack, AttackTable, Bag, Board, Game, GameType,
+    GarbageQueue, Input, InputDirection, InputRotation, Piece,
+};
+
+const SNAP_TETRIS: [Input; 3] = [
+    Input {
+        rotation: InputRotation::Quarter,
+        ..NONE
+    },
+    Input {
+        direction: InputDirection::SnapRight,
+        ..NONE
+    },
+    Input {
+        hard_drop: true,
+        ..NONE
+    },
+];
+
+#[test]
+fn back_to_back_tetrises() {
+    let mut rows = vec!["######### "; 8];
+    rows.push("#### #####");
+
+    let bag = Bag::from_queue(&[PieceType::I, PieceType::I, PieceType::I], 0);
+    let board = Board::from_strs_with_piece(&rows, bag, Piece::I);
+    let mut game = Game::from_board(board, GameType::new_versus());
+
+    let mut tick = 0;
+    for _ in 0..2 {
+        for input in SNAP_TETRIS {
+            tick += 1;
+            game.tick(input, tick);
+        }
+    }
+
+    assert_eq!(game.score().lines(), 8);
+    assert!(game.attack().back_to_back());
+    assert_eq!(game.attack().combo(), Some(1));
+    assert_eq!(game.attack().last(), 4 + 1);
+    assert_eq!(game.attack().sent(), 4 + 4 + 1);
+}
+
+#[test]
+fn default_table() {
+    let table = AttackTable::default();
+
+    assert_eq!(table.clears, [0, 0, 1, 2, 4]);
+    assert_eq!(table.t_spins[2], 4);
+    assert_eq!(table.perfect_clear, 10);
+    assert_eq!(Attack::default(), Attack::new(table));
+}
+
+#[test]
+fn queue_cancels_oldest_first() {
+    let mut queue = GarbageQueue::new(0);
+
+    queue.push(2, 0);
+    queue.push(3, 0);
+
+    assert_eq!(queue.cancel(3), 0);
+    assert_eq!(queue.pending(), 2);
+    assert_eq!(queue.cancel(4), 2);
+    assert_eq!(queue.pending(), 0);
+}
+
+#[test]
+fn queue_waits_for_delay() {
+    let mut queue = GarbageQueue::new(500);
+
+    queue.push(2, 100);
+    queue.push(1, 300);
+
+    assert_eq!(queue.ready(599), 0);
+    assert_eq!(queue.take(599), 0);
+    assert_eq!(queue.ready(600), 2);
+    assert_eq!(queue.take(700), 2);
+    assert_eq!(queue.take(800), 1);
+    assert_eq!(queue.pending(), 0);
+}
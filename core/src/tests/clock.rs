@@ -0,0 +1,65 @@
+#[allow(unused_imports)]
+use crate::{
+    tests::util::NONE, Bag, Clock, Game, GameType, Input, Recorder, Replay, Ruleset, TickType,
+};
+
+#[test]
+fn pauses_stop_the_clock() {
+    let mut clock = Clock::new();
+
+    assert_eq!(clock.next(0), None);
+    assert_eq!(clock.next(2), Some(1));
+    assert_eq!(clock.next(2), Some(2));
+    assert_eq!(clock.next(2), None);
+
+    clock.pause(2);
+    assert!(clock.paused());
+    assert_eq!(clock.next(900), None);
+    assert_eq!(clock.time(900), 2);
+
+    clock.resume(1000);
+    assert!(!clock.paused());
+    assert_eq!(clock.time(1001), 3);
+    assert_eq!(clock.next(1001), Some(3));
+    assert_eq!(clock.next(1001), None);
+}
+
+#[test]
+fn pauses_are_left_out_of_replays() {
+    let rules = Ruleset::guideline();
+    let mut game = Game::new(Bag::new(3), GameType::new_sandbox(), &rules);
+    let mut recorder = Recorder::new(3, 0, &game);
+    let mut clock = Clock::new();
+
+    // A second of play, a pause of one and a half, then another half second before quitting
+    'game: for now in 0..=3000 {
+        match now {
+            1000 => clock.pause(now),
+            2500 => clock.resume(now),
+            _ => {}
+        }
+
+        while let Some(tick) = clock.next(now) {
+            let input = Input {
+                hard_drop: tick.is_multiple_of(100),
+                quit: now == 3000,
+                ..NONE
+            };
+
+            recorder.record(input, tick);
+
+            if game.tick(input, tick).kind() == TickType::GameOver {
+                break 'game;
+            }
+        }
+    }
+
+    assert_eq!(clock.tick(), 1500);
+
+    let replay = Replay::new(recorder.raw("player", &game, clock.tick() as u64, 0)).unwrap();
+    let verification = replay.verify(&rules);
+
+    assert_eq!(replay.duration(), 1500);
+    assert_eq!(verification.mismatches(), &[]);
+    assert_eq!(verification.duration(), Some(1500));
+}
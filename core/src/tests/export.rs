@@ -0,0 +1,151 @@
+#[allow(unused_imports)]
+use crate::{
+    tests::util::{recorded_game, written_by},
+    Edit, GameMode, ImportError, Metadata, Mismatch, PieceType, Replay, Ruleset,
+};
+
+fn recording() -> Vec<u8> {
+    let (game, mut recorder, end) = recorded_game();
+
+    recorder.set_metadata(Metadata::Notes, String::from("C:\\new\nline"));
+
+    recorder.raw("\"quoted\" player", &game, end, 5678)
+}
+
+#[test]
+fn json_round_trip() {
+    let replay = Replay::new(recording()).unwrap();
+    let imported = Replay::from_json(&replay.to_json()).unwrap();
+
+    assert_eq!(imported, replay);
+    assert_eq!(imported.to_bytes(), recording());
+}
+
+#[test]
+fn text_round_trip() {
+    let replay = Replay::new(recording()).unwrap();
+    let text = replay.to_text();
+
+    assert!(text.contains("\n8 hard_drop\n"));
+    assert!(text.contains("\n7 piece:I\n"));
+    assert!(text.contains("\nmetadata.notes = C:\\\\new\\nline\n"));
+
+    let imported = Replay::from_text(&text).unwrap();
+
+    assert_eq!(imported, replay);
+    assert_eq!(imported.to_bytes(), recording());
+}
+
+#[test]
+fn hand_written_replays() {
+    let text = "
+        # Only the username, mode and seed are needed
+        username = tester
+        mode = sandbox
+        seed = 3
+
+        [frames]
+        0
+        70000 toggle:3:44 left
+        70001 piece:t rotate_90 hard_drop
+        70002 quit
+    ";
+
+    let mut replay = Replay::from_text(text).unwrap();
+
+    assert_eq!(replay.kind().mode(), GameMode::Sandbox);
+    assert!(replay.matches(&Ruleset::guideline()));
+
+    let bytes = Replay::new(replay.to_bytes()).unwrap();
+    assert_eq!(bytes, replay);
+
+    replay.next();
+
+    let frame = replay.next().unwrap();
+    assert_eq!(frame.time, 70000);
+    assert_eq!(frame.input.edit, Some(Edit::Toggle(3, 44)));
+
+    let frame = replay.next().unwrap();
+    assert_eq!(frame.input.edit, Some(Edit::Piece(PieceType::T)));
+}
+
+#[test]
+fn import_errors() {
+    let json = r#"{ "username": "a", "mode": "lines", "seed": "1", "frames": [{ "time": 0 }] }"#;
+    assert!(Replay::from_json(json).is_ok());
+
+    assert_eq!(
+        Replay::from_json("{\n\"username\": \"a\",\n}"),
+        Err(ImportError::Syntax(3))
+    );
+    assert_eq!(
+        Replay::from_json(&json.replace("lines", "tetris")),
+        Err(ImportError::InvalidField("mode"))
+    );
+    assert_eq!(
+        Replay::from_json(&json.replace("\"seed\": \"1\", ", "")),
+        Err(ImportError::MissingField("seed"))
+    );
+    assert_eq!(
+        Replay::from_text("username = a\nmode = lines\nseed = 1\n[frames]\n0 jump"),
+        Err(ImportError::InvalidInput(String::from("jump")))
+    );
+    assert_eq!(
+        Replay::from_text("username = a\nmode = lines\nseed = 1\n[frames]\n5\n4"),
+        Err(ImportError::InvalidField("frames"))
+    );
+    assert_eq!(
+        Replay::from_text("username = a\nnonsense"),
+        Err(ImportError::Syntax(2))
+    );
+}
+
+#[test]
+fn deep_nesting_is_refused() {
+    let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+
+    assert_eq!(Replay::from_json(&deep), Err(ImportError::Syntax(1)));
+}
+
+#[test]
+fn legacy_versions_stay_unverifiable() {
+    let legacy = Replay::new(written_by(4)).unwrap();
+    let rules = Ruleset::guideline();
+
+    for imported in [
+        Replay::from_json(&legacy.to_json()),
+        Replay::from_text(&legacy.to_text()),
+    ] {
+        let imported = imported.unwrap();
+
+        assert_eq!(imported.version(), 4);
+        assert_eq!(
+            imported.verify(&rules).mismatches(),
+            &[Mismatch::Version(4)]
+        );
+    }
+
+    // Written back out they take the last format that did not tick every millisecond
+    let mut rewritten = Replay::new(legacy.to_bytes()).unwrap();
+
+    assert_eq!(rewritten.version(), 5);
+    assert_eq!(
+        rewritten.verify(&rules).mismatches(),
+        &[Mismatch::Version(5)]
+    );
+    assert_eq!(rewritten.next().map(|frame| frame.time), Some(0));
+    assert_eq!(rewritten.next().map(|frame| frame.time), Some(100));
+    assert_eq!(rewritten.next(), None);
+
+    let text = "username = a\nversion = 5\nmode = lines\nseed = 1\n[frames]\n0\n70000 left";
+    let mut long = Replay::new(Replay::from_text(text).unwrap().to_bytes()).unwrap();
+    let times: Vec<u128> = std::iter::from_fn(|| long.next())
+        .map(|frame| frame.time)
+        .collect();
+
+    assert_eq!(times, [0, 65535, 70000]);
+    assert_eq!(
+        Replay::from_text(&text.replace("version = 5", "version = 9")),
+        Err(ImportError::InvalidField("version"))
+    );
+}
@@ -0,0 +1,109 @@
+#[allow(unused_imports)]
+use crate::{
+    piece::PieceType, point::Point, tests::util::NONE, Bag, Board, Finesse, Game, GameType, Input,
+    InputDirection, Piece, Ruleset,
+};
+
+const LEFT: Input = Input {
+    direction: InputDirection::Left,
+    ..NONE
+};
+
+const SOFT: Input = Input {
+    soft_drop: true,
+    ..NONE
+};
+
+const HARD: Input = Input {
+    hard_drop: true,
+    ..NONE
+};
+
+#[test]
+fn placement_counts() {
+    let counts = [17, 34, 34, 9, 17, 34, 17];
+
+    for (kind, count) in PieceType::ALL.into_iter().zip(counts) {
+        assert_eq!(
+            Finesse::placements(kind, &Ruleset::guideline()).len(),
+            count
+        );
+    }
+}
+
+#[test]
+fn optimal_presses() {
+    let bottom = Board::HEIGHT as i8 - 3;
+    let rules = Ruleset::guideline();
+
+    // Spawn column, left wall, one column off the wall and the vertical I on the right wall
+    assert_eq!(Finesse::optimal(Piece::T, Point::new(3, bottom), &rules), 0);
+    assert_eq!(
+        Finesse::optimal(Piece::O, Point::new(-1, bottom), &rules),
+        1
+    );
+    assert_eq!(Finesse::optimal(Piece::O, Point::new(0, bottom), &rules), 2);
+    assert_eq!(
+        Finesse::optimal(Piece::I.rotate(1), Point::new(7, 0), &rules),
+        2
+    );
+}
+
+#[test]
+fn presses_follow_the_rules() {
+    // Flipping the T at spawn takes one press with 180 rotation and two without
+    let flipped = |rules: &Ruleset| {
+        Finesse::placements(PieceType::T, rules)
+            .into_iter()
+            .find(|(piece, position, _)| {
+                *piece == Piece::T.rotate(2) && position.x() == rules.spawn_column()
+            })
+            .map(|(_, _, presses)| presses)
+    };
+
+    assert_eq!(flipped(&Ruleset::guideline()), Some(1));
+    assert_eq!(flipped(&Ruleset::classic()), Some(2));
+}
+
+#[test]
+fn tapping_is_a_fault() {
+    let board = Board::from_strs_with_piece(&[], Bag::new(0), Piece::T);
+    let mut game = Game::from_board(board, GameType::new_marathon());
+
+    for (tick, input) in [LEFT, LEFT, LEFT, HARD].into_iter().enumerate() {
+        game.tick(input, tick as u128 + 1);
+    }
+
+    let res = game.finesse().last().unwrap();
+
+    assert_eq!(res.piece(), PieceType::T);
+    assert_eq!(res.presses(), 3);
+    assert_eq!(res.optimal(), 1);
+    assert_eq!(res.faults(), 2);
+    assert_eq!(game.finesse().faulty(), 1);
+}
+
+#[test]
+fn tucks_are_not_judged() {
+    let board = Board::from_strs_with_piece(
+        &["###       ", "          ", "          "],
+        Bag::new(0),
+        Piece::T,
+    );
+    let mut game = Game::from_board(board, GameType::new_marathon());
+
+    for tick in 1..40 {
+        game.tick(SOFT, tick);
+    }
+
+    for (tick, input) in [LEFT, LEFT, LEFT, HARD].into_iter().enumerate() {
+        game.tick(input, tick as u128 + 40);
+    }
+
+    let placement = game.board().placement().unwrap();
+
+    assert!(placement.covers(Piece::T, Point::new(0, Board::HEIGHT as i8 - 3)));
+    assert!(!placement.dropped());
+    assert_eq!(game.finesse().last(), None);
+    assert_eq!(game.finesse().judged(), 0);
+}
@@ -0,0 +1,35 @@
+#[allow(unused_imports)]
+use crate::{GameMode, GameType, GameTypeError};
+
+#[test]
+fn constructors() {
+    assert_eq!(GameType::new_marathon().mode(), GameMode::Marathon);
+    assert_eq!(GameType::new_lines(40).mode(), GameMode::Lines);
+    assert_eq!(GameType::new_lines(40).lines(), 40);
+    assert_eq!(GameType::new_timed(120).mode(), GameMode::Time);
+    assert_eq!(GameType::new_timed(120).time(), 120);
+}
+
+#[test]
+fn round_trip() {
+    for kind in [
+        GameType::new_marathon(),
+        GameType::new_lines(40),
+        GameType::new_timed(120),
+    ] {
+        assert_eq!(kind.to_string().parse::<GameType>(), Ok(kind));
+    }
+
+    assert_eq!("40L".parse::<GameType>(), Ok(GameType::new_lines(40)));
+    assert_eq!("120s".parse::<GameType>(), Ok(GameType::new_timed(120)));
+    assert_eq!("marathon".parse::<GameType>(), Ok(GameType::new_marathon()));
+}
+
+#[test]
+fn invalid() {
+    assert_eq!("".parse::<GameType>(), Err(GameTypeError::UnknownMode));
+    assert_eq!("40X".parse::<GameType>(), Err(GameTypeError::UnknownMode));
+    assert_eq!("0L".parse::<GameType>(), Err(GameTypeError::InvalidCount));
+    assert_eq!("L".parse::<GameType>(), Err(GameTypeError::InvalidCount));
+    assert_eq!("-5S".parse::<GameType>(), Err(GameTypeError::InvalidCount));
+}
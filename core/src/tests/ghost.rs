@@ -0,0 +1,114 @@
+#[allow(unused_imports)]
+use crate::{
+    tests::util::{recorded_game, NONE},
+    Bag, Finish, Game, GameType, Ghost, Input, InputDirection, Recorder, Replay, Ruleset,
+};
+
+fn ghost() -> Ghost {
+    let (game, recorder, end) = recorded_game();
+    let replay = Replay::new(recorder.raw("player", &game, end, 0)).unwrap();
+
+    Ghost::new(replay, &Ruleset::guideline())
+}
+
+#[test]
+fn splits() {
+    let ghost = ghost();
+
+    // A line is cleared 8ms into every 100ms
+    assert_eq!(ghost.split(0), Some(0));
+    assert_eq!(ghost.split(1), Some(8));
+    assert_eq!(ghost.split(15), Some(1408));
+    assert_eq!(ghost.split(16), None);
+
+    assert_eq!(ghost.delta(1, 10), Some(2));
+    assert_eq!(ghost.delta(15, 1400), Some(-8));
+    assert_eq!(ghost.delta(16, 1400), None);
+}
+
+#[test]
+fn advances_with_the_game() {
+    let (game, _, _) = recorded_game();
+    let mut ghost = ghost();
+
+    ghost.advance(450);
+    assert_eq!(ghost.tick(), 450);
+    assert_eq!(ghost.game().score().lines(), 5);
+
+    ghost.advance(u128::MAX);
+    assert_eq!(ghost.tick(), ghost.duration());
+    assert_eq!(ghost.game().score(), game.score());
+}
+
+// Clears the bottom row with the first pieces of seed 85, an I, J and L, just after the given tick
+fn clear_a_line(kind: GameType, start: u128) -> (Game, Replay, u128) {
+    let rules = Ruleset::guideline();
+    let mut game = Game::new(Bag::new(85), kind, &rules);
+    let mut recorder = Recorder::new(85, 0, &game);
+    let moves = [
+        InputDirection::SnapLeft,
+        InputDirection::Right,
+        InputDirection::SnapRight,
+    ];
+    let end = start + 100 * moves.len() as u128;
+
+    for tick in 1..=end {
+        let input = match (tick - 1).checked_sub(start).map(|t| (t / 100, t % 100)) {
+            Some((i, 0)) if i < 3 => Input {
+                direction: moves[i as usize],
+                ..NONE
+            },
+            Some((_, 1)) => Input {
+                hard_drop: true,
+                ..NONE
+            },
+            _ => NONE,
+        };
+
+        recorder.record(input, tick);
+        game.tick(input, tick);
+    }
+
+    let replay = Replay::new(recorder.raw("player", &game, end as u64, 0)).unwrap();
+
+    (game, replay, end)
+}
+
+#[test]
+fn finishes_by_time() {
+    let rules = Ruleset::guideline();
+    let (ghost_game, replay, _) = clear_a_line(GameType::new_lines(1), 0);
+    let ghost = Ghost::new(replay, &rules);
+
+    assert_eq!(ghost_game.score().lines(), 1);
+    assert_eq!(ghost.split(1), Some(202));
+
+    let (game, _, end) = clear_a_line(GameType::new_lines(1), 50);
+    assert_eq!(ghost.finish(&game, 252), Finish::Time(50));
+
+    // Short of the goal only the lines are compared
+    let game = Game::new(Bag::new(85), GameType::new_lines(1), &rules);
+    assert_eq!(ghost.finish(&game, end), Finish::Lines(0, 1));
+}
+
+#[test]
+fn finishes_by_result() {
+    let rules = Ruleset::guideline();
+
+    let (timed, replay, _) = clear_a_line(GameType::new_timed(1), 0);
+    let ghost = Ghost::new(replay, &rules);
+    let game = Game::new(Bag::new(85), GameType::new_timed(1), &rules);
+
+    assert!(timed.score().score() > 0);
+    assert_eq!(
+        ghost.finish(&game, 1000),
+        Finish::Score(0, timed.score().score())
+    );
+
+    // Surviving longer is ahead
+    let (_, replay, end) = clear_a_line(GameType::new_survival(), 0);
+    let ghost = Ghost::new(replay, &rules);
+    let game = Game::new(Bag::new(85), GameType::new_survival(), &rules);
+
+    assert_eq!(ghost.finish(&game, end + 500), Finish::Time(-500));
+}
@@ -0,0 +1,246 @@
+#[allow(unused_imports)]
+use crate::{
+    tests::util::NONE, GameMode, Input, InputDirection, InputRotation, Lockstep, LockstepError,
+    Match, Message, Replay, Ruleset,
+};
+#[allow(unused_imports)]
+use std::{
+    io::Write,
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+// Sends every message through its byte encoding, the way it would travel over a socket
+fn deliver(to: &mut Lockstep, message: Message) -> Result<(), LockstepError> {
+    let bytes = message.to_bytes();
+    let received = Message::read(&mut bytes.as_slice())?;
+
+    assert_eq!(received, message);
+
+    to.receive(received)
+}
+
+fn script(step: usize, player: usize) -> Input {
+    match (step + player * 3) % 7 {
+        0 => Input {
+            hard_drop: true,
+            ..NONE
+        },
+        1 => Input {
+            direction: InputDirection::Left,
+            ..NONE
+        },
+        2 => Input {
+            rotation: InputRotation::Quarter,
+            ..NONE
+        },
+        4 => Input {
+            direction: InputDirection::SnapRight,
+            ..NONE
+        },
+        _ => NONE,
+    }
+}
+
+#[test]
+fn peers_stay_in_sync() {
+    let mut host = Lockstep::new(9, 300, 0, &Ruleset::guideline());
+    let mut guest = Lockstep::from_hello(host.hello(), 1, &Ruleset::guideline()).unwrap();
+
+    for step in 0..400 {
+        let message = host.local(script(step, 0));
+        deliver(&mut guest, message).unwrap();
+
+        let message = guest.local(script(step, 1));
+        deliver(&mut host, message).unwrap();
+
+        for message in host.advance().unwrap() {
+            deliver(&mut guest, message).unwrap();
+        }
+
+        for message in guest.advance().unwrap() {
+            deliver(&mut host, message).unwrap();
+        }
+    }
+
+    assert!(host.duration() > 0);
+    assert_eq!(host.duration(), guest.duration());
+    assert_eq!(host.versus(), guest.versus());
+}
+
+#[test]
+fn waits_for_both_inputs() {
+    let mut host = Lockstep::new(0, 300, 0, &Ruleset::guideline());
+
+    for _ in 0..(Lockstep::INPUT_DELAY + 5) {
+        host.local(NONE);
+    }
+
+    host.advance().unwrap();
+    assert_eq!(
+        host.duration() as u128,
+        Lockstep::INPUT_DELAY as u128 * Lockstep::STEP
+    );
+}
+
+#[test]
+fn versus_replay_round_trip() {
+    let mut host = Lockstep::new(4, 300, 0, &Ruleset::guideline());
+    let mut guest = Lockstep::from_hello(host.hello(), 1, &Ruleset::guideline()).unwrap();
+
+    for step in 0..200 {
+        host.receive(guest.local(script(step, 1))).unwrap();
+        guest.receive(host.local(script(step, 0))).unwrap();
+        host.advance().unwrap();
+        guest.advance().unwrap();
+    }
+
+    let duration = host.duration() as u128;
+    let expected = host.versus().clone();
+    let mut replay = Replay::new(host.raw("host vs guest", 0)).unwrap();
+
+    assert_eq!(replay.kind().mode(), GameMode::Versus);
+    assert_eq!(replay.kind().garbage_delay(), 300);
+
+    let mut versus = Match::from_replay(&replay, &Ruleset::guideline());
+    let mut frames = [replay.next(), replay.next_opponent()];
+
+    for tick in 1..=duration {
+        let mut inputs = [NONE; 2];
+
+        for player in 0..2 {
+            if let Some(frame) = frames[player].filter(|frame| frame.time <= tick) {
+                inputs[player] = frame.input;
+                frames[player] = match player {
+                    0 => replay.next(),
+                    _ => replay.next_opponent(),
+                };
+            }
+        }
+
+        versus.tick(inputs, tick);
+    }
+
+    assert_eq!(versus.player(0).blocks(), expected.player(0).blocks());
+    assert_eq!(versus.player(1).blocks(), expected.player(1).blocks());
+}
+
+#[test]
+fn peers_play_by_the_same_rules() {
+    let host = Lockstep::new(2, 300, 0, &Ruleset::classic());
+
+    assert_eq!(
+        Lockstep::from_hello(host.hello(), 1, &Ruleset::guideline()),
+        Err(LockstepError::RulesMismatch)
+    );
+
+    let guest = Lockstep::from_hello(host.hello(), 1, &Ruleset::classic()).unwrap();
+
+    for player in 0..2 {
+        assert_eq!(guest.versus().player(player).rules(), &Ruleset::classic());
+    }
+}
+
+#[test]
+fn versus_replays_keep_the_rules() {
+    let mut host = Lockstep::new(6, 300, 0, &Ruleset::classic());
+    let mut guest = Lockstep::from_hello(host.hello(), 1, &Ruleset::classic()).unwrap();
+
+    for step in 0..400 {
+        host.receive(guest.local(script(step, 1))).unwrap();
+        guest.receive(host.local(script(step, 0))).unwrap();
+        host.advance().unwrap();
+        guest.advance().unwrap();
+    }
+
+    assert!(host.versus().over());
+
+    let replay = Replay::new(host.raw("host vs guest", 0)).unwrap();
+
+    assert_eq!(replay.ruleset(), Some(Ruleset::classic()));
+    assert_eq!(replay.verify(&Ruleset::classic()).mismatches(), &[]);
+}
+
+#[test]
+fn unexpected_attacks_are_a_desync() {
+    let mut host = Lockstep::new(1, 300, 0, &Ruleset::guideline());
+    let mut guest = Lockstep::from_hello(host.hello(), 1, &Ruleset::guideline()).unwrap();
+
+    for step in 0..10 {
+        host.receive(guest.local(NONE)).unwrap();
+        guest.receive(host.local(script(step, 0))).unwrap();
+        host.advance().unwrap();
+    }
+
+    // Nothing was placed yet, so the guest cannot have sent rows
+    let attack = Message::Attack { step: 2, rows: 4 };
+    assert_eq!(host.receive(attack), Err(LockstepError::Desync(2)));
+
+    // A report for a step not played yet waits for it
+    let mut host = Lockstep::new(1, 300, 0, &Ruleset::guideline());
+    assert_eq!(host.receive(Message::Attack { step: 2, rows: 4 }), Ok(()));
+}
+
+// One peer's side of a match over a socket, each step sends an input and waits for the other's
+fn play_over(stream: &mut TcpStream, lockstep: &mut Lockstep, steps: usize) {
+    let player = lockstep.local_player();
+
+    for step in 0..steps {
+        let message = lockstep.local(script(step, player));
+        stream.write_all(&message.to_bytes()).unwrap();
+
+        loop {
+            let message = Message::read(stream).unwrap();
+            lockstep.receive(message).unwrap();
+
+            if matches!(message, Message::Input(_)) {
+                break;
+            }
+        }
+
+        for message in lockstep.advance().unwrap() {
+            stream.write_all(&message.to_bytes()).unwrap();
+        }
+    }
+}
+
+#[test]
+fn peers_play_over_loopback() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let guest = thread::spawn(move || {
+        let mut stream = TcpStream::connect(address).unwrap();
+        let hello = Message::read(&mut stream).unwrap();
+        let mut guest = Lockstep::from_hello(hello, 1, &Ruleset::guideline()).unwrap();
+
+        play_over(&mut stream, &mut guest, 400);
+        guest
+    });
+
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut host = Lockstep::new(8, 300, 0, &Ruleset::guideline());
+
+    stream.write_all(&host.hello().to_bytes()).unwrap();
+    play_over(&mut stream, &mut host, 400);
+
+    let guest = guest.join().unwrap();
+
+    assert!(host.duration() > 0);
+    assert_eq!(host.duration(), guest.duration());
+    assert_eq!(host.versus(), guest.versus());
+}
+
+#[test]
+fn rejects_unknown_messages() {
+    let bytes = [9u8, 0, 0];
+
+    assert_eq!(
+        Message::read(&mut bytes.as_slice()),
+        Err(LockstepError::UnknownMessage)
+    );
+    assert_eq!(
+        Message::read(&mut [1u8, 0].as_slice()),
+        Err(LockstepError::Disconnected)
+    );
+}
@@ -1,5 +1,22 @@
 #![allow(dead_code)]
+mod analysis;
+mod attack;
+mod clock;
+mod export;
+mod finesse;
+mod game_type;
+mod ghost;
+mod lockstep;
+mod modes;
+mod playback;
+mod puzzle;
+mod replay;
+mod ruleset;
+mod simulator;
 mod spin_180;
+mod stats;
 mod t_spin;
 mod util;
+mod verify;
+mod versus;
 mod zs_spin;
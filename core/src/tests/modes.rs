@@ -0,0 +1,397 @@
+#[allow(unused_imports)]
+use crate::{
+    piece::PieceType, tests::util::NONE, Bag, Board, Colour, Edit, Game, GameMode, GameType,
+    GameTypeError, Grade, Grading, Input, InputDirection, InputRotation, Piece, Recorder, Replay,
+    Ruleset, TickResult, TickType,
+};
+
+#[test]
+fn round_trip() {
+    for kind in [
+        GameType::new_endless(),
+        GameType::new(GameMode::Marathon, 200).with_level(5),
+        GameType::new_dig(GameType::DIG_ROWS),
+        GameType::new_dig(100),
+        GameType::new_survival(),
+        GameType::new_master(),
+        GameType::new_sandbox(),
+    ] {
+        assert_eq!(kind.to_string().parse::<GameType>(), Ok(kind));
+    }
+
+    assert_eq!(
+        "Endless L12".parse::<GameType>(),
+        Ok(GameType::new_endless().with_level(12))
+    );
+}
+
+#[test]
+fn invalid_levels() {
+    assert_eq!(
+        "Marathon L0".parse::<GameType>(),
+        Err(GameTypeError::InvalidLevel)
+    );
+    assert_eq!(
+        "Marathon L21".parse::<GameType>(),
+        Err(GameTypeError::InvalidLevel)
+    );
+    assert_eq!(
+        "40L L5".parse::<GameType>(),
+        Err(GameTypeError::InvalidLevel)
+    );
+}
+
+#[test]
+fn marathon_level_multiplier() {
+    let board = Board::from_strs_with_piece(
+        &["######### ", "######### ", "######### ", "######### "],
+        Bag::new(0),
+        Piece::I,
+    );
+
+    let kind = GameType::new(GameMode::Marathon, 4).with_level(5);
+    let mut game = Game::from_board(board, kind);
+
+    assert_eq!(game.score().level(), 5);
+
+    let inputs = [
+        Input {
+            rotation: InputRotation::Quarter,
+            ..NONE
+        },
+        Input {
+            direction: InputDirection::SnapRight,
+            ..NONE
+        },
+        Input {
+            hard_drop: true,
+            ..NONE
+        },
+    ];
+
+    let mut res = game.tick(NONE, 1);
+    for (tick, input) in inputs.into_iter().enumerate() {
+        res = game.tick(input, tick as u128 + 2);
+    }
+
+    assert_eq!(res.kind(), TickType::GameOver);
+    assert_eq!(game.score().lines(), 4);
+    assert_eq!(game.score().score(), 800 * 5);
+    assert_eq!(game.score().level(), 5);
+}
+
+#[test]
+fn dig_feeds_seeded_garbage() {
+    let game = Game::new(Bag::new(7), GameType::new_dig(25), &Ruleset::guideline());
+    let again = Game::new(Bag::new(7), GameType::new_dig(25), &Ruleset::guideline());
+
+    assert_eq!(game.board().garbage_rows(), 10);
+    assert_eq!(game.garbage_left(), 25);
+    assert_eq!(game.blocks(), again.blocks());
+}
+
+#[test]
+fn dig_ends_when_garbage_is_cleared() {
+    let board = Board::from_strs_with_piece(&[], Bag::new(0), Piece::I);
+    let mut game = Game::from_board(board, GameType::new_dig(1));
+    let hole = (0..Board::WIDTH)
+        .find(|x| game.block(*x, Board::HEIGHT - 1) == crate::Colour::None)
+        .unwrap();
+
+    // A vertical I sits in the third column of its box
+    let (direction, moves) = if hole < 5 {
+        (InputDirection::Left, 5 - hole)
+    } else {
+        (InputDirection::Right, hole - 5)
+    };
+
+    let mut inputs = vec![Input {
+        rotation: InputRotation::Quarter,
+        ..NONE
+    }];
+
+    inputs.extend((0..moves).map(|_| Input { direction, ..NONE }));
+    inputs.push(Input {
+        hard_drop: true,
+        ..NONE
+    });
+
+    let mut res = game.tick(NONE, 1);
+    for (tick, input) in inputs.into_iter().enumerate() {
+        res = game.tick(input, tick as u128 + 2);
+    }
+
+    assert_eq!(res.kind(), TickType::GameOver);
+    assert_eq!(game.garbage_left(), 0);
+}
+
+#[test]
+fn survival_garbage_rises_on_a_timer() {
+    let mut game = Game::new(Bag::new(3), GameType::new_survival(), &Ruleset::guideline());
+    let mut again = Game::new(Bag::new(3), GameType::new_survival(), &Ruleset::guideline());
+
+    for tick in 1..=10_000 {
+        game.tick(NONE, tick);
+        again.tick(NONE, tick);
+    }
+
+    assert_eq!(game.garbage_received(), 2);
+    assert_eq!(game.score().score(), 10);
+    assert_eq!(game, again);
+}
+
+#[test]
+fn master_entry_delay() {
+    let mut game = Game::new(Bag::new(0), GameType::new_master(), &Ruleset::guideline());
+    let hard_drop = Input {
+        hard_drop: true,
+        ..NONE
+    };
+
+    assert_eq!(game.score().level(), 0);
+    assert_eq!(game.grade(), Grade::Nine);
+
+    assert!(game.tick(hard_drop, 1).placed());
+    assert_eq!(game.score().level(), 1);
+
+    // Still waiting for the next piece to enter
+    assert!(!game.tick(hard_drop, 200).placed());
+    assert_eq!(game.score().level(), 1);
+
+    assert!(game.tick(hard_drop, 1 + 417).placed());
+    assert_eq!(game.score().level(), 2);
+}
+
+#[test]
+fn grade_names() {
+    assert_eq!(Grade::Nine.to_string(), "9");
+    assert_eq!(Grade::One.to_string(), "1");
+    assert_eq!(Grade::S1.to_string(), "S1");
+    assert_eq!(Grade::S9.to_string(), "S9");
+    assert_eq!(Grade::GrandMaster.to_string(), "GM");
+}
+
+// Tetrises at the given level until the points reach the target
+fn tetrises(grading: &mut Grading, level: u64, points: u64) {
+    while grading.points() < points {
+        grading.update(TickResult::locked(PieceType::I, 4), level, false);
+    }
+}
+
+#[test]
+fn master_points() {
+    let mut grading = Grading::new();
+
+    grading.update(TickResult::locked(PieceType::I, 4), 0, false);
+    assert_eq!(grading.points(), 28);
+
+    // Placing without a clear ends the combo
+    grading.update(TickResult::locked(PieceType::T, 0), 4, false);
+    assert_eq!(grading.points(), 28);
+
+    grading.update(TickResult::locked(PieceType::I, 1), 5, false);
+    assert_eq!(grading.points(), 30);
+
+    grading.update(TickResult::locked(PieceType::I, 2), 6, false);
+    assert_eq!(grading.points(), 42);
+    assert_eq!(grading.grade(), Grade::Nine);
+
+    // A perfect clear is worth four times as much
+    grading.update(TickResult::locked(PieceType::I, 4), 8, true);
+    assert_eq!(grading.points(), 474);
+    assert_eq!(grading.grade(), Grade::Eight);
+
+    tetrises(&mut grading, 100, 16000);
+    assert_eq!(grading.grade(), Grade::S1);
+}
+
+#[test]
+fn master_grand_master() {
+    let mut grading = Grading::new();
+
+    tetrises(&mut grading, 200, 12000);
+    grading.reach(300, 255_000);
+    tetrises(&mut grading, 400, 40000);
+    grading.reach(500, 450_000);
+    tetrises(&mut grading, 900, 126000);
+    assert_eq!(grading.grade(), Grade::S9);
+
+    grading.reach(999, 810_000);
+    assert_eq!(grading.grade(), Grade::GrandMaster);
+
+    // Grades never go back down
+    grading.update(TickResult::locked(PieceType::T, 0), 999, false);
+    assert_eq!(grading.grade(), Grade::GrandMaster);
+}
+
+#[test]
+fn master_missed_checkpoints() {
+    // Too slow through the first section
+    let mut slow = Grading::new();
+
+    tetrises(&mut slow, 200, 12000);
+    slow.reach(300, 255_001);
+    tetrises(&mut slow, 400, 126000);
+    slow.reach(999, 810_000);
+    assert_eq!(slow.grade(), Grade::S9);
+
+    // Fast enough, but short of the points at the second checkpoint
+    let mut short = Grading::new();
+
+    tetrises(&mut short, 200, 12000);
+    short.reach(500, 250_000);
+    tetrises(&mut short, 900, 126000);
+    short.reach(999, 800_000);
+    assert_eq!(short.grade(), Grade::S9);
+}
+
+#[test]
+fn master_grade_is_recorded() {
+    let mut rows = vec!["######### "; 16];
+    rows.push("# ########");
+
+    let board = Board::from_strs(&rows, Bag::from_queue(&[PieceType::I; 5], 0));
+    let mut game = Game::from_board(board, GameType::new_master());
+    let mut recorder = Recorder::new(0, 0, &game);
+
+    // Four tetrises down the right side, two seconds apart to wait out the clear and entry delays
+    for i in 0..4 {
+        let tick = 2000 * i + 1;
+        let inputs = [
+            Input {
+                rotation: InputRotation::Quarter,
+                ..NONE
+            },
+            Input {
+                direction: InputDirection::SnapRight,
+                ..NONE
+            },
+            Input {
+                hard_drop: true,
+                ..NONE
+            },
+        ];
+
+        for (j, input) in inputs.into_iter().enumerate() {
+            recorder.record(input, tick + j as u128);
+            game.tick(input, tick + j as u128);
+        }
+
+        assert_eq!(game.score().lines(), 4 * (i as u64 + 1));
+    }
+
+    assert_eq!(game.score().score(), 760);
+    assert_eq!(game.grade(), Grade::Eight);
+
+    let replay = Replay::new(recorder.raw("player", &game, 8000, 0)).unwrap();
+    assert_eq!(replay.grade(), Grade::Eight);
+    assert_eq!(replay.score().score(), 760);
+}
+
+#[test]
+fn sandbox_edits() {
+    let mut game = Game::new(Bag::new(0), GameType::new_sandbox(), &Ruleset::guideline());
+    let bottom = Board::HEIGHT - 1;
+
+    assert!(game.edit(Edit::Piece(PieceType::T)));
+    assert_eq!(game.piece().kind(), PieceType::T);
+
+    assert!(game.edit(Edit::SwapHold));
+    assert_eq!(game.held().map(|piece| piece.kind()), Some(PieceType::T));
+
+    assert!(game.edit(Edit::SwapHold));
+    assert_eq!(game.piece().kind(), PieceType::T);
+
+    assert!(game.edit(Edit::Toggle(0, bottom)));
+    assert_eq!(game.block(0, bottom), Colour::Grey);
+    assert!(game.edit(Edit::Toggle(0, bottom)));
+    assert_eq!(game.block(0, bottom), Colour::None);
+
+    assert!(game.edit(Edit::Garbage));
+    assert_eq!(game.board().garbage_rows(), 1);
+
+    let mut race = Game::new(Bag::new(0), GameType::new_lines(40), &Ruleset::guideline());
+    assert!(!race.edit(Edit::Garbage));
+}
+
+#[test]
+fn sandbox_edits_keep_the_board_state() {
+    let mut game = Game::new(Bag::new(0), GameType::new_sandbox(), &Ruleset::guideline());
+    let hold = Input { hold: true, ..NONE };
+
+    assert!(game.edit(Edit::Garbage));
+    game.tick(hold, 1);
+
+    let stats = game.stats();
+
+    assert!(game.edit(Edit::Toggle(0, 0)));
+    assert_eq!(game.stats(), stats);
+    assert_eq!(game.board().garbage_rows(), 1);
+
+    // Holding stays used up until the next piece, a swap in the sandbox does not hand it back
+    let held = game.held();
+
+    assert!(game.edit(Edit::SwapHold));
+    assert!(game.edit(Edit::SwapHold));
+    game.tick(hold, 2);
+    assert_eq!(game.held(), held);
+
+    assert!(game.edit(Edit::Piece(PieceType::T)));
+    assert_eq!(game.stats().total(), stats.total() + 1);
+    assert_eq!(game.board().garbage_rows(), 1);
+}
+
+#[test]
+fn sandbox_garbage_reports_a_top_out() {
+    let mut game = Game::new(Bag::new(0), GameType::new_sandbox(), &Ruleset::guideline());
+
+    assert!(game.edit(Edit::Toggle(0, 0)));
+    assert!(!game.edit(Edit::Garbage));
+}
+
+#[test]
+fn sandbox_has_no_game_over() {
+    let mut game = Game::new(Bag::new(0), GameType::new_sandbox(), &Ruleset::guideline());
+    let hard_drop = Input {
+        hard_drop: true,
+        ..NONE
+    };
+
+    for tick in 1..=100 {
+        assert_ne!(game.tick(hard_drop, tick).kind(), TickType::GameOver);
+    }
+
+    let quit = Input { quit: true, ..NONE };
+    assert_eq!(game.tick(quit, 101).kind(), TickType::GameOver);
+}
+
+#[test]
+fn sandbox_edits_are_recorded() {
+    let game = Game::new(Bag::new(0), GameType::new_sandbox(), &Ruleset::guideline());
+    let mut recorder = Recorder::new(0, 0, &game);
+
+    let toggle = Input {
+        direction: InputDirection::Left,
+        edit: Some(Edit::Toggle(9, Board::HEIGHT - 2)),
+        ..NONE
+    };
+    let piece = Input {
+        edit: Some(Edit::Piece(PieceType::Z)),
+        ..NONE
+    };
+
+    recorder.record(toggle, 10);
+    recorder.record(piece, 25);
+
+    let mut replay = Replay::new(recorder.raw("test", &game, 25, 0)).unwrap();
+
+    assert_eq!(replay.kind(), GameType::new_sandbox());
+    assert_eq!(replay.next().map(|frame| frame.input), Some(NONE));
+
+    let frame = replay.next().unwrap();
+    assert_eq!((frame.time, frame.input), (10, toggle));
+
+    let frame = replay.next().unwrap();
+    assert_eq!((frame.time, frame.input), (25, piece));
+    assert_eq!(replay.next(), None);
+}
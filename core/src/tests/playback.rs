@@ -0,0 +1,58 @@
+#[allow(unused_imports)]
+use crate::{tests::util::recorded_game, Playback, Replay, Ruleset};
+
+fn playback() -> Playback {
+    let (game, recorder, end) = recorded_game();
+    let replay = Replay::new(recorder.raw("player", &game, end, 0)).unwrap();
+
+    Playback::new(replay, &Ruleset::guideline())
+}
+
+#[test]
+fn plays_to_the_end() {
+    let (game, _, _) = recorded_game();
+    let mut playback = playback();
+
+    playback.seek(u128::MAX);
+
+    assert!(playback.over());
+    assert_eq!((playback.tick(), playback.duration()), (1500, 1500));
+    assert_eq!(playback.game().score(), game.score());
+    assert_eq!(playback.pieces(), 15);
+}
+
+#[test]
+fn seeking_back_matches_playing_forward() {
+    let mut forward = playback();
+    forward.seek(1234);
+
+    let mut rewound = playback();
+    rewound.seek(1500);
+    rewound.seek(1234);
+
+    assert_eq!(rewound.tick(), 1234);
+    assert_eq!(rewound.game(), forward.game());
+
+    rewound.seek(5);
+    forward.seek(1500);
+    forward.seek(5);
+
+    assert_eq!(rewound.game(), forward.game());
+    assert_eq!(rewound.pieces(), 0);
+}
+
+#[test]
+fn seeking_pieces() {
+    let mut playback = playback();
+
+    // A piece is placed 8ms into every 100ms
+    playback.seek_piece(5);
+    assert_eq!((playback.tick(), playback.pieces()), (308, 4));
+
+    playback.seek(1400);
+    playback.seek_piece(2);
+    assert_eq!((playback.tick(), playback.pieces()), (8, 1));
+
+    playback.seek_piece(1);
+    assert_eq!((playback.tick(), playback.pieces()), (0, 0));
+}
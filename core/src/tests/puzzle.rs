@@ -0,0 +1,117 @@
+#[allow(unused_imports)]
+use crate::{
+    piece::PieceType, tests::util::NONE, Game, Goal, Input, InputDirection, InputRotation, Puzzle,
+    PuzzleError, PuzzleStatus, Ruleset, TickType,
+};
+
+const PACK: &str = "
+# Comments and blank lines are skipped
+name = Tetris
+queue = I
+goal = lines 4
+|######### |
+|######### |
+|######### |
+|######### |
+---
+name = Out of pieces
+queue = O J
+hold = T
+goal = pc
+pieces = 1
+|######### |
+";
+
+const SNAP_TETRIS: [Input; 3] = [
+    Input {
+        rotation: InputRotation::Quarter,
+        ..NONE
+    },
+    Input {
+        direction: InputDirection::SnapRight,
+        ..NONE
+    },
+    Input {
+        hard_drop: true,
+        ..NONE
+    },
+];
+
+fn play(game: &mut Game, inputs: &[Input]) -> TickType {
+    let mut res = game.tick(NONE, 1).kind();
+
+    for (tick, input) in inputs.iter().enumerate() {
+        res = game.tick(*input, tick as u128 + 2).kind();
+    }
+
+    res
+}
+
+#[test]
+fn parse_pack() {
+    let pack = Puzzle::pack(PACK).unwrap();
+
+    assert_eq!(pack.len(), 2);
+    assert_eq!(pack[0].name(), "Tetris");
+    assert_eq!(pack[0].goal(), Goal::Lines(4));
+    assert_eq!(pack[0].pieces(), 1);
+    assert_eq!(pack[1].goal(), Goal::PerfectClear);
+    assert_eq!(pack[1].pieces(), 1);
+
+    let board = pack[1].board();
+    assert_eq!(board.piece().kind(), PieceType::O);
+    assert_eq!(board.held().map(|piece| piece.kind()), Some(PieceType::T));
+    assert_eq!(board.peek(0).kind(), PieceType::J);
+}
+
+#[test]
+fn invalid_puzzles() {
+    assert_eq!(
+        "queue = I\ngoal = tsd\n|### |".parse::<Puzzle>(),
+        Err(PuzzleError::InvalidRow)
+    );
+    assert_eq!(
+        "queue = I\ngoal = tsd\n|ä         |".parse::<Puzzle>(),
+        Err(PuzzleError::InvalidRow)
+    );
+    assert_eq!(
+        "queue = IX\ngoal = tsd".parse::<Puzzle>(),
+        Err(PuzzleError::InvalidPiece)
+    );
+    assert_eq!(
+        "queue = I\ngoal = tss".parse::<Puzzle>(),
+        Err(PuzzleError::InvalidGoal)
+    );
+    assert_eq!(
+        "goal = pc".parse::<Puzzle>(),
+        Err(PuzzleError::MissingQueue)
+    );
+    assert_eq!("queue = T".parse::<Puzzle>(), Err(PuzzleError::MissingGoal));
+}
+
+#[test]
+fn solve_and_fail() {
+    let pack = Puzzle::pack(PACK).unwrap();
+
+    let mut game = Game::from_puzzle(&pack[0], &Ruleset::guideline());
+    assert_eq!(game.puzzle_status(), Some(PuzzleStatus::InProgress));
+    assert_eq!(play(&mut game, &SNAP_TETRIS), TickType::GameOver);
+    assert_eq!(game.puzzle_status(), Some(PuzzleStatus::Solved));
+
+    let mut game = Game::from_puzzle(&pack[1], &Ruleset::guideline());
+    assert_eq!(play(&mut game, &SNAP_TETRIS[2..]), TickType::GameOver);
+    assert_eq!(game.puzzle_status(), Some(PuzzleStatus::Failed));
+    assert_eq!(game.puzzle_pieces(), Some((1, 1)));
+}
+
+#[test]
+fn puzzles_follow_the_ruleset() {
+    let pack = Puzzle::pack(PACK).unwrap();
+    let mut game = Game::from_puzzle(&pack[1], &Ruleset::classic());
+
+    assert_eq!(game.rules(), &Ruleset::classic());
+
+    // Classic has no hold to swap in the puzzle's held piece
+    game.tick(Input { hold: true, ..NONE }, 1);
+    assert_eq!(game.piece().kind(), PieceType::O);
+}
@@ -0,0 +1,305 @@
+#[allow(unused_imports)]
+use crate::{
+    replay::crc32,
+    tests::util::{written_by, NONE},
+    Bag, Game, GameMode, GameType, Input, InputDirection, InputRotation, Metadata, Recorder,
+    Replay, ReplayError, Ruleset,
+};
+
+fn recording() -> Vec<u8> {
+    let game = Game::new(Bag::new(1), GameType::new_lines(40), &Ruleset::guideline());
+    let mut recorder = Recorder::new(1, 0, &game);
+
+    let left = Input {
+        direction: InputDirection::Left,
+        ..NONE
+    };
+
+    recorder.record(left, 100);
+
+    recorder.raw("プレイヤー", &game, 1234, 5678)
+}
+
+// A version 4 replay, written with the username up to a newline and no checksum
+fn legacy() -> Vec<u8> {
+    let mut buffer = Vec::from("old\n".as_bytes());
+    buffer.extend_from_slice(&[4, GameMode::Lines as u8]);
+
+    for num in [40u64, 0, 0, 1234, 1, 5678, 1, 1] {
+        buffer.extend_from_slice(&num.to_be_bytes());
+    }
+
+    buffer.push(0);
+    buffer.extend_from_slice(&Ruleset::guideline().hash().to_be_bytes());
+    buffer.extend_from_slice(&[0, 0, 0, 0, 100, 1]);
+
+    buffer
+}
+
+#[test]
+fn round_trip() {
+    let mut replay = Replay::new(recording()).unwrap();
+
+    assert_eq!(replay.username(), "プレイヤー");
+    assert_eq!(replay.kind(), GameType::new_lines(40));
+    assert_eq!((replay.duration(), replay.time_stamp()), (1234, 5678));
+    assert_eq!(replay.next().map(|frame| frame.time), Some(0));
+    assert_eq!(replay.next().map(|frame| frame.time), Some(100));
+    assert_eq!(replay.next(), None);
+}
+
+#[test]
+fn legacy_replays_load() {
+    let mut replay = Replay::new(legacy()).unwrap();
+
+    assert_eq!(replay.username(), "old");
+    assert_eq!(replay.kind(), GameType::new_lines(40));
+    assert!(replay.matches(&Ruleset::guideline()));
+    assert_eq!(replay.next().map(|frame| frame.time), Some(0));
+    assert_eq!(replay.next().map(|frame| frame.time), Some(100));
+}
+
+#[test]
+fn corruption_is_detected() {
+    let mut flipped = recording();
+    let middle = flipped.len() / 2;
+    flipped[middle] ^= 1;
+
+    assert_eq!(Replay::new(flipped), Err(ReplayError::ChecksumMismatch));
+
+    let mut cut = recording();
+    cut.truncate(cut.len() - 1);
+
+    assert_eq!(Replay::new(cut), Err(ReplayError::ChecksumMismatch));
+    assert_eq!(
+        Replay::new(recording()[..7].to_vec()),
+        Err(ReplayError::Truncated)
+    );
+}
+
+#[test]
+fn precise_errors() {
+    assert_eq!(Replay::new(Vec::new()), Err(ReplayError::BadMagic));
+    assert_eq!(Replay::new(b"hello".to_vec()), Err(ReplayError::BadMagic));
+    assert_eq!(
+        Replay::new(b"name\n\x09".to_vec()),
+        Err(ReplayError::BadMagic)
+    );
+
+    let mut future = recording();
+    future[4] = 200;
+
+    assert_eq!(
+        Replay::new(future),
+        Err(ReplayError::UnsupportedVersion(200))
+    );
+
+    let mut mode = legacy();
+    mode[5] = 42;
+
+    assert_eq!(Replay::new(mode), Err(ReplayError::InvalidMode(42)));
+    assert_eq!(
+        Replay::new(legacy()[..20].to_vec()),
+        Err(ReplayError::Truncated)
+    );
+    assert_eq!(
+        Replay::new(b"\xff\n\x04".to_vec()),
+        Err(ReplayError::InvalidUsername)
+    );
+}
+
+// A version 6 replay, with the magic and checksum but frame times as two bytes
+fn version_6() -> Vec<u8> {
+    let mut buffer = vec![0x89, b'R', b'T', b'R', 6, 0, 3];
+    buffer.extend_from_slice(b"six");
+    buffer.push(GameMode::Lines as u8);
+
+    for num in [40u64, 0, 0, 1234, 1, 5678, 1, 1] {
+        buffer.extend_from_slice(&num.to_be_bytes());
+    }
+
+    buffer.push(0);
+    buffer.extend_from_slice(&Ruleset::guideline().hash().to_be_bytes());
+    buffer.extend_from_slice(&[0, 0, 0, 0xff, 0xff, 0, 0x11, 0x71, 1]);
+
+    let crc = crc32(&buffer);
+    buffer.extend_from_slice(&crc.to_be_bytes());
+
+    buffer
+}
+
+#[test]
+fn version_6_replays_load() {
+    let mut replay = Replay::new(version_6()).unwrap();
+
+    assert_eq!(replay.username(), "six");
+    assert_eq!(replay.next().map(|frame| frame.time), Some(0));
+    assert_eq!(replay.next().map(|frame| frame.time), Some(65535));
+    assert_eq!(replay.next().map(|frame| frame.time), Some(70000));
+}
+
+#[test]
+fn long_gaps_keep_their_time() {
+    let game = Game::new(Bag::new(1), GameType::new_lines(40), &Ruleset::guideline());
+    let mut recorder = Recorder::new(1, 0, &game);
+
+    let left = Input {
+        direction: InputDirection::Left,
+        ..NONE
+    };
+
+    for time in [100, 65635, 70000, 10 * 60 * 60 * 1000] {
+        recorder.record(left, time);
+    }
+
+    let mut replay = Replay::new(recorder.raw("player", &game, 0, 0)).unwrap();
+    let times: Vec<u128> = std::iter::from_fn(|| replay.next())
+        .map(|frame| frame.time)
+        .collect();
+
+    assert_eq!(times, [0, 100, 65635, 70000, 10 * 60 * 60 * 1000]);
+}
+
+#[test]
+fn repeated_inputs_take_a_byte() {
+    let game = Game::new(Bag::new(1), GameType::new_lines(40), &Ruleset::guideline());
+    let left = Input {
+        direction: InputDirection::Left,
+        ..NONE
+    };
+
+    let size = |presses: u128| {
+        let mut recorder = Recorder::new(1, 0, &game);
+
+        for press in 0..presses {
+            recorder.record(left, 100 + press * 33);
+        }
+
+        recorder.raw("player", &game, 0, 0).len()
+    };
+
+    assert_eq!(size(11) - size(1), 10);
+}
+
+#[test]
+fn metadata_round_trip() {
+    let game = Game::new(Bag::new(1), GameType::new_lines(40), &Ruleset::guideline());
+    let mut recorder = Recorder::new(1, 0, &game);
+
+    recorder.set_metadata(Metadata::Notes, String::from("first try\nno holds"));
+    recorder.set_metadata(Metadata::Client, String::from("rustris 0.1.0"));
+
+    let replay = Replay::new(recorder.raw("player", &game, 0, 0)).unwrap();
+
+    assert_eq!(
+        replay.metadata(Metadata::Notes),
+        Some("first try\nno holds")
+    );
+    assert_eq!(replay.metadata(Metadata::Client), Some("rustris 0.1.0"));
+    assert_eq!(replay.metadata(Metadata::Platform), None);
+    assert_eq!(replay.ruleset(), Some(Ruleset::guideline()));
+
+    let keys: Vec<Metadata> = replay
+        .metadata_entries()
+        .iter()
+        .map(|(key, _)| *key)
+        .collect();
+
+    assert_eq!(keys, [Metadata::Client, Metadata::Ruleset, Metadata::Notes]);
+    assert_eq!(Replay::new(replay.to_bytes()), Ok(replay));
+}
+
+#[test]
+fn unknown_metadata_is_skipped() {
+    let mut buffer = vec![0x89, b'R', b'T', b'R', 8, 0, 5];
+    buffer.extend_from_slice(b"later");
+    buffer.push(GameMode::Lines as u8);
+
+    for num in [40u64, 0, 0, 1234, 1, 5678, 1, 1] {
+        buffer.extend_from_slice(&num.to_be_bytes());
+    }
+
+    buffer.push(0);
+    buffer.extend_from_slice(&Ruleset::guideline().hash().to_be_bytes());
+
+    // A tag from a newer version between two known ones
+    buffer.extend_from_slice(&[0, 13, 200, 0, 3, b'n', b'e', b'w', 5, 0, 4]);
+    buffer.extend_from_slice(b"note");
+    buffer.extend_from_slice(&[0, 0]);
+
+    let crc = crc32(&buffer);
+    buffer.extend_from_slice(&crc.to_be_bytes());
+
+    let replay = Replay::new(buffer).unwrap();
+
+    assert_eq!(replay.metadata_entries().len(), 1);
+    assert_eq!(replay.metadata(Metadata::Notes), Some("note"));
+}
+
+#[test]
+fn every_version_loads() {
+    for number in 1..=8 {
+        let mut replay = Replay::new(written_by(number)).unwrap();
+
+        assert_eq!(replay.version(), number);
+        assert_eq!(replay.username(), "old");
+        assert_eq!(replay.kind(), GameType::new_lines(40));
+        assert_eq!((replay.duration(), replay.time_stamp()), (1234, 5678));
+        assert!(replay.matches(&Ruleset::guideline()));
+        assert_eq!(replay.next().map(|frame| frame.time), Some(0));
+        assert_eq!(replay.next().map(|frame| frame.time), Some(100));
+        assert_eq!(replay.next(), None);
+    }
+
+    assert_eq!(
+        Replay::new(written_by(8))
+            .unwrap()
+            .metadata(Metadata::Client),
+        Some("test")
+    );
+}
+
+#[test]
+fn varints_shrink_frames() {
+    let game = Game::new(Bag::new(1), GameType::new_lines(40), &Ruleset::guideline());
+
+    // A hundred pieces of turning, shifting twice and dropping, presses 60 to 250ms apart
+    let script = [
+        Input {
+            rotation: InputRotation::Quarter,
+            ..NONE
+        },
+        Input {
+            direction: InputDirection::Left,
+            ..NONE
+        },
+        Input {
+            direction: InputDirection::Left,
+            ..NONE
+        },
+        Input {
+            hard_drop: true,
+            ..NONE
+        },
+    ];
+
+    let size = |pieces: usize| {
+        let mut recorder = Recorder::new(1, 0, &game);
+        let mut tick = 0;
+
+        for (press, input) in script
+            .iter()
+            .cycle()
+            .take(pieces * script.len())
+            .enumerate()
+        {
+            tick += 60 + (press * 37 % 190) as u128;
+            recorder.record(*input, tick);
+        }
+
+        recorder.raw("player", &game, 0, 0).len()
+    };
+
+    // Version 6 took three bytes for every frame, 1200 for these
+    assert_eq!(size(100) - size(0), 1091);
+}
@@ -0,0 +1,74 @@
+#[allow(unused_imports)]
+use crate::{
+    tests::util::NONE, Bag, Game, GameType, Input, InputRotation, Kicks, Piece, Recorder, Replay,
+    Ruleset, RulesetError,
+};
+
+#[test]
+fn presets_round_trip() {
+    for name in Ruleset::PRESETS {
+        let rules = Ruleset::preset(name).unwrap();
+
+        assert_eq!(rules.to_string().parse::<Ruleset>(), Ok(rules));
+    }
+
+    assert_eq!(Ruleset::preset("nes"), Err(RulesetError::UnknownPreset));
+}
+
+#[test]
+fn options_override_the_preset() {
+    let rules: Ruleset = "# Slower classic\npreset = classic\nname = Slow\ngravity = 1000\n"
+        .parse()
+        .unwrap();
+
+    assert_eq!(rules.name(), "Slow");
+    assert_eq!(rules.gravity(), 1000);
+    assert_eq!(rules.kicks(), Kicks::None);
+    assert!(!rules.hold());
+    assert_ne!(rules.hash(), Ruleset::classic().hash());
+
+    let renamed: Ruleset = "preset = classic\nname = Renamed".parse().unwrap();
+    assert_eq!(renamed.hash(), Ruleset::classic().hash());
+}
+
+#[test]
+fn invalid_rulesets() {
+    let parse = |s: &str| s.parse::<Ruleset>();
+
+    assert_eq!(parse("gravity"), Err(RulesetError::InvalidOption));
+    assert_eq!(parse("speed = 10"), Err(RulesetError::InvalidOption));
+    assert_eq!(parse("gravity = fast"), Err(RulesetError::InvalidValue));
+    assert_eq!(parse("kicks = ars"), Err(RulesetError::InvalidValue));
+    assert_eq!(parse("spawn_column = 9"), Err(RulesetError::InvalidValue));
+    assert_eq!(parse("clear_scores = 1 2"), Err(RulesetError::InvalidValue));
+    assert_eq!(parse("preset = nes"), Err(RulesetError::UnknownPreset));
+}
+
+#[test]
+fn classic_has_no_hold_or_180() {
+    let mut game = Game::new(Bag::new(0), GameType::new_lines(40), &Ruleset::classic());
+    let piece = game.piece();
+
+    game.tick(Input { hold: true, ..NONE }, 1);
+    assert_eq!(game.piece(), piece);
+    assert_eq!(game.held(), None);
+
+    let half_turn = Input {
+        rotation: InputRotation::TwoQuarter,
+        ..NONE
+    };
+
+    game.tick(half_turn, 2);
+    assert_eq!(game.piece().rotation(), 0);
+}
+
+#[test]
+fn replays_store_the_ruleset() {
+    let game = Game::new(Bag::new(0), GameType::new_lines(40), &Ruleset::tgm());
+    let recorder = Recorder::new(0, 0, &game);
+    let replay = Replay::new(recorder.raw("test", &game, 0, 0)).unwrap();
+
+    assert_eq!(replay.rules(), Ruleset::tgm().hash());
+    assert!(replay.matches(&Ruleset::tgm()));
+    assert!(!replay.matches(&Ruleset::guideline()));
+}
@@ -0,0 +1,97 @@
+#[allow(unused_imports)]
+use crate::{tests::util::recorded_game, InputDirection, Replay, Ruleset, Simulator};
+
+fn simulator() -> Simulator {
+    let (game, recorder, end) = recorded_game();
+    let replay = Replay::new(recorder.raw("player", &game, end, 0)).unwrap();
+
+    Simulator::new(replay, &Ruleset::guideline())
+}
+
+#[test]
+fn any_pace_gives_the_same_game() {
+    let (game, _, _) = recorded_game();
+    let mut at_once = simulator();
+
+    at_once.run_to(1500);
+
+    assert_eq!(at_once.tick(), 1500);
+    assert_eq!(at_once.game().score(), game.score());
+
+    // Uneven steps, like a renderer that falls behind now and then
+    for pace in [1, 7, 16, 333] {
+        let mut paced = simulator();
+
+        while paced.tick() < 1500 {
+            paced.run_to((paced.tick() + pace).min(1500));
+        }
+
+        assert_eq!(paced.game(), at_once.game());
+    }
+}
+
+#[test]
+fn frames_sharing_a_tick_are_all_taken() {
+    let text = "
+        username = tester
+        mode = sandbox
+        seed = 3
+
+        [frames]
+        0
+        5 left
+        5 right
+        9 hard_drop
+    ";
+
+    let replay = Replay::from_text(text).unwrap();
+    let mut simulator = Simulator::new(replay, &Ruleset::guideline());
+
+    simulator.run_to(4);
+    assert_eq!(simulator.taken().len(), 0);
+
+    simulator.step();
+
+    let taken: Vec<InputDirection> = simulator
+        .taken()
+        .iter()
+        .map(|frame| frame.input.direction)
+        .collect();
+
+    assert_eq!(taken, [InputDirection::Left, InputDirection::Right]);
+}
+
+// A sandbox replay of the frames, run for 20ms
+fn from_frames(frames: &str) -> Simulator {
+    let text = format!(
+        "username = tester\nmode = sandbox\nseed = 3\n\n[frames]\n0\n{}",
+        frames
+    );
+    let mut simulator = Simulator::new(Replay::from_text(&text).unwrap(), &Ruleset::guideline());
+
+    simulator.run_to(20);
+    simulator
+}
+
+#[test]
+fn frames_sharing_a_tick_are_merged() {
+    let apart = from_frames("5 left\n5 hard_drop\n");
+    let together = from_frames("5 left hard_drop\n");
+
+    // Neither input is lost to the other
+    assert_eq!(apart.game(), together.game());
+    assert_ne!(apart.game(), from_frames("5 left\n").game());
+    assert_ne!(apart.game(), from_frames("5 hard_drop\n").game());
+}
+
+#[test]
+fn repeated_presses_in_a_tick_count_once() {
+    for press in ["left", "rotate_90", "hard_drop"] {
+        let once = from_frames(&format!("5 {}\n", press));
+        let twice = from_frames(&format!("5 {}\n5 {}\n", press, press));
+        let apart = from_frames(&format!("5 {}\n6 {}\n", press, press));
+
+        assert_eq!(twice.game(), once.game());
+        assert_ne!(apart.game(), once.game());
+    }
+}
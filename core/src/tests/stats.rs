@@ -0,0 +1,28 @@
+#[allow(unused_imports)]
+use crate::{piece::PieceType, Bag, Board, Input};
+
+const HARD: Input = Input {
+    hard_drop: true,
+    ..crate::tests::util::NONE
+};
+
+#[test]
+fn bag_distribution() {
+    let mut board = Board::new(Bag::new(0));
+
+    // The first piece is dealt on creation, 13 more gives two full bags
+    for tick in 0..13 {
+        board.tick(HARD, tick);
+    }
+
+    let stats = board.stats();
+
+    assert_eq!(stats.total(), 14);
+
+    for kind in PieceType::ALL {
+        assert_eq!(stats.count(kind), 2);
+        assert!(stats.gap(kind) < 7);
+    }
+
+    assert!(stats.drought() <= 12);
+}
@@ -1,4 +1,7 @@
-use crate::{Board, Colour, Input, InputDirection, InputRotation};
+use crate::{
+    replay::crc32, Bag, Board, Colour, Edit, Game, GameMode, GameType, Input, InputDirection,
+    InputRotation, Metadata, PieceType, Recorder, Ruleset, TickType,
+};
 
 pub fn print_board(board: &Board) {
     const PLAY_FIELD_BOTTOM: &str = "############";
@@ -22,13 +25,14 @@ pub fn print_board(board: &Board) {
     println!("{}", PLAY_FIELD_BOTTOM);
 }
 
-const NONE: Input = Input {
+pub const NONE: Input = Input {
     hold: false,
     quit: false,
     hard_drop: false,
     soft_drop: false,
     rotation: InputRotation::None,
     direction: InputDirection::None,
+    edit: None,
 };
 
 const LEFT: Input = {
@@ -174,3 +178,96 @@ macro_rules! tester {
 }
 
 pub(crate) use tester;
+
+// Fills the bottom row around the middle and clears it with an I piece every 100ms, in a sandbox
+pub fn recorded_game() -> (Game, Recorder, u64) {
+    let mut game = Game::new(Bag::new(7), GameType::new_sandbox(), &Ruleset::guideline());
+    let mut recorder = Recorder::new(7, 0, &game);
+
+    let edit = |edit| Input {
+        edit: Some(edit),
+        ..NONE
+    };
+
+    for tick in 1.. {
+        let input = match (tick, tick % 100) {
+            (1500, _) => Input { quit: true, ..NONE },
+            (_, step @ 1..=6) => edit(Edit::Toggle(
+                [0, 1, 2, 7, 8, 9][step as usize - 1],
+                Board::HEIGHT - 1,
+            )),
+            (_, 7) => edit(Edit::Piece(PieceType::I)),
+            (_, 8) => Input {
+                hard_drop: true,
+                ..NONE
+            },
+            _ => NONE,
+        };
+
+        recorder.record(input, tick);
+
+        if game.tick(input, tick).kind() == TickType::GameOver {
+            return (game, recorder, tick as u64);
+        }
+    }
+
+    unreachable!()
+}
+
+// A replay as every version of the format wrote it, holding the same game
+pub fn written_by(version: u8) -> Vec<u8> {
+    let mut buffer = Vec::new();
+
+    if version < 5 {
+        buffer.extend_from_slice(b"old\n");
+        buffer.push(version);
+    } else {
+        buffer.extend_from_slice(&[0x89, b'R', b'T', b'R', version, 0, 3]);
+        buffer.extend_from_slice(b"old");
+    }
+
+    // Line races were written as marathons with a goal before there were levels
+    let mode = match version {
+        1 => GameMode::Marathon,
+        _ => GameMode::Lines,
+    };
+
+    buffer.push(mode as u8);
+
+    for num in [40u64, 0, 0, 1234, 1, 5678] {
+        buffer.extend_from_slice(&num.to_be_bytes());
+    }
+
+    // The starting and the final level
+    if version >= 2 {
+        for num in [1u64, 1] {
+            buffer.extend_from_slice(&num.to_be_bytes());
+        }
+    }
+
+    if version >= 3 {
+        buffer.push(0);
+    }
+
+    if version >= 4 {
+        buffer.extend_from_slice(&Ruleset::guideline().hash().to_be_bytes());
+    }
+
+    if version >= 8 {
+        buffer.extend_from_slice(&[0, 7, Metadata::Client as u8, 0, 4]);
+        buffer.extend_from_slice(b"test");
+    }
+
+    if version >= 7 {
+        buffer.extend_from_slice(&[0, 0, 0xc8, 0x01, 1]);
+    } else {
+        buffer.extend_from_slice(&[0, 0, 0, 0, 100, 1]);
+    }
+
+    if version >= 5 {
+        let crc = crc32(&buffer);
+        buffer.extend_from_slice(&crc.to_be_bytes());
+    }
+
+    buffer
+}
@@ -0,0 +1,54 @@
+#[allow(unused_imports)]
+use crate::{tests::util::recorded_game, Bag, Game, GameType, Mismatch, Replay, Ruleset};
+
+#[test]
+fn honest_replays_verify() {
+    let (game, recorder, end) = recorded_game();
+    let replay = Replay::new(recorder.raw("player", &game, end, 0)).unwrap();
+    let verification = replay.verify(&Ruleset::guideline());
+
+    assert_eq!(game.score().lines(), 15);
+    assert_eq!(verification.mismatches(), &[]);
+    assert_eq!(verification.score(), game.score());
+    assert_eq!(verification.duration(), Some(1500));
+}
+
+#[test]
+fn tampered_replays_do_not_verify() {
+    let (game, recorder, end) = recorded_game();
+    let fresh = Game::new(Bag::new(7), GameType::new_sandbox(), &Ruleset::guideline());
+
+    // The header of a game that never scored, over the inputs of one that did
+    let replay = Replay::new(recorder.raw("player", &fresh, end - 1, 0)).unwrap();
+    let verification = replay.verify(&Ruleset::guideline());
+
+    assert!(!verification.valid());
+    assert_eq!(
+        verification.mismatches(),
+        &[
+            Mismatch::Score {
+                recorded: 0,
+                simulated: game.score().score(),
+            },
+            Mismatch::Lines {
+                recorded: 0,
+                simulated: game.score().lines(),
+            },
+            Mismatch::Duration {
+                recorded: 1499,
+                simulated: Some(1500),
+            },
+        ]
+    );
+}
+
+#[test]
+fn other_rules_do_not_verify() {
+    let (game, recorder, end) = recorded_game();
+    let replay = Replay::new(recorder.raw("player", &game, end, 0)).unwrap();
+
+    assert_eq!(
+        replay.verify(&Ruleset::classic()).mismatches(),
+        &[Mismatch::Rules]
+    );
+}
@@ -0,0 +1,89 @@
+#[allow(unused_imports)]
+use crate::{
+    tests::util::NONE, Bag, Board, Input, InputDirection, InputRotation, Match, Piece, TickType,
+};
+
+const HARD: Input = Input {
+    hard_drop: true,
+    ..NONE
+};
+
+#[test]
+fn same_pieces() {
+    let mut versus = Match::new(5);
+
+    for tick in 1..=20 {
+        versus.tick([HARD, HARD], tick);
+
+        assert_eq!(versus.player(0).piece(), versus.player(1).piece());
+        assert_eq!(versus.player(0).blocks(), versus.player(1).blocks());
+    }
+}
+
+#[test]
+fn tetris_sends_garbage() {
+    let rows = [
+        "######### ",
+        "######### ",
+        "######### ",
+        "######### ",
+        "#### #####",
+    ];
+    let mut versus = Match::from_boards([
+        Board::from_strs_with_piece(&rows, Bag::new(0), Piece::I),
+        Board::new(Bag::new(0)),
+    ]);
+
+    let inputs = [
+        Input {
+            rotation: InputRotation::Quarter,
+            ..NONE
+        },
+        Input {
+            direction: InputDirection::SnapRight,
+            ..NONE
+        },
+        HARD,
+    ];
+
+    for (tick, input) in inputs.into_iter().enumerate() {
+        versus.tick([input, NONE], tick as u128 + 1);
+    }
+
+    assert_eq!(versus.sent(0), 4);
+    assert_eq!(versus.queue(1).pending(), 4);
+    assert_eq!(versus.player(1).board().garbage_rows(), 0);
+
+    // Garbage waits out its delay and then rises with the next piece placed
+    versus.tick([NONE, HARD], 4);
+    assert_eq!(versus.player(1).board().garbage_rows(), 0);
+
+    versus.tick([NONE, HARD], 3 + Match::GARBAGE_DELAY);
+    assert_eq!(versus.player(1).board().garbage_rows(), 4);
+    assert_eq!(versus.queue(1).pending(), 0);
+    assert!(!versus.over());
+}
+
+#[test]
+fn top_out_loses() {
+    let mut versus = Match::new(0);
+    let mut tick = 0;
+
+    while !versus.over() {
+        tick += 1;
+        versus.tick([HARD, NONE], tick);
+    }
+
+    assert_eq!(versus.winner(), Some(1));
+}
+
+#[test]
+fn quit_has_no_winner() {
+    let mut versus = Match::new(0);
+    let quit = Input { quit: true, ..NONE };
+
+    versus.tick([NONE, quit], 1);
+
+    assert!(versus.over());
+    assert_eq!(versus.winner(), None);
+}
@@ -0,0 +1,242 @@
+use std::fmt::Display;
+
+use crate::{
+    replay::VERIFIABLE_VERSION, Game, GameMode, Grade, Input, Lockstep, Match, Replay, Ruleset,
+    Score, Simulator,
+};
+
+// How long a game is simulated past its last input before it is taken to never end
+const IDLE_LIMIT: u128 = 10 * 60 * 1000;
+
+// A part of a replay the recorded inputs do not play back to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mismatch {
+    // The replay was recorded by other rules than the ones it was checked by
+    Rules,
+    // Recorded before every millisecond was ticked, so the inputs need not play back the same
+    Version(u8),
+    Score {
+        recorded: u64,
+        simulated: u64,
+    },
+    Lines {
+        recorded: u64,
+        simulated: u64,
+    },
+    Level {
+        recorded: u64,
+        simulated: u64,
+    },
+    Grade {
+        recorded: Grade,
+        simulated: Grade,
+    },
+    // None when the game had not ended long after the last input
+    Duration {
+        recorded: u64,
+        simulated: Option<u64>,
+    },
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mismatch::Rules => write!(f, "Replay was recorded with a different ruleset"),
+            Mismatch::Version(version) => {
+                write!(f, "Replay version {} can not be verified", version)
+            }
+            Mismatch::Score {
+                recorded,
+                simulated,
+            } => write!(f, "Score is {}, the inputs score {}", recorded, simulated),
+            Mismatch::Lines {
+                recorded,
+                simulated,
+            } => write!(f, "Lines are {}, the inputs clear {}", recorded, simulated),
+            Mismatch::Level {
+                recorded,
+                simulated,
+            } => write!(f, "Level is {}, the inputs reach {}", recorded, simulated),
+            Mismatch::Grade {
+                recorded,
+                simulated,
+            } => write!(f, "Grade is {}, the inputs earn {}", recorded, simulated),
+            Mismatch::Duration {
+                recorded,
+                simulated: Some(simulated),
+            } => write!(
+                f,
+                "Game ends at {}ms, the inputs end it at {}ms",
+                recorded, simulated
+            ),
+            Mismatch::Duration {
+                recorded,
+                simulated: None,
+            } => write!(f, "Game ends at {}ms, the inputs never end it", recorded),
+        }
+    }
+}
+
+// The result of playing a replay's inputs back and comparing them with what it claims
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Verification {
+    score: Score,
+    grade: Grade,
+    duration: Option<u64>,
+    mismatches: Vec<Mismatch>,
+}
+
+impl Verification {
+    fn failed(mismatch: Mismatch) -> Self {
+        Self {
+            score: Score::new(),
+            grade: Grade::Nine,
+            duration: None,
+            mismatches: vec![mismatch],
+        }
+    }
+
+    pub fn valid(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    pub fn mismatches(&self) -> &[Mismatch] {
+        &self.mismatches
+    }
+
+    // Score of the simulated game, of the first player in versus
+    pub fn score(&self) -> Score {
+        self.score
+    }
+
+    pub fn grade(&self) -> Grade {
+        self.grade
+    }
+
+    // Tick the simulated game ended on, None if it did not
+    pub fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+}
+
+impl Replay {
+    // Plays the inputs back as fast as possible and checks they give the recorded result
+    pub fn verify(&self, rules: &Ruleset) -> Verification {
+        if !self.matches(rules) {
+            return Verification::failed(Mismatch::Rules);
+        }
+
+        let versus = self.kind().mode() == GameMode::Versus;
+
+        // Versus was always played in lockstep, which ticks every millisecond
+        if !versus && self.version() < VERIFIABLE_VERSION {
+            return Verification::failed(Mismatch::Version(self.version()));
+        }
+
+        let (game, end) = if versus {
+            let (versus, end) = self.simulate_versus(rules);
+
+            // Matches are recorded in whole steps
+            let end = end.map(|end| end.div_ceil(Lockstep::STEP) * Lockstep::STEP);
+
+            (versus.player(0).clone(), end)
+        } else {
+            self.simulate(rules)
+        };
+
+        let (score, recorded) = (game.score(), self.score());
+        let mut mismatches = Vec::new();
+
+        if recorded.score() != score.score() {
+            mismatches.push(Mismatch::Score {
+                recorded: recorded.score(),
+                simulated: score.score(),
+            });
+        }
+
+        if recorded.lines() != score.lines() {
+            mismatches.push(Mismatch::Lines {
+                recorded: recorded.lines(),
+                simulated: score.lines(),
+            });
+        }
+
+        if recorded.level() != score.level() {
+            mismatches.push(Mismatch::Level {
+                recorded: recorded.level(),
+                simulated: score.level(),
+            });
+        }
+
+        if self.grade() != game.grade() {
+            mismatches.push(Mismatch::Grade {
+                recorded: self.grade(),
+                simulated: game.grade(),
+            });
+        }
+
+        let duration = end.map(|end| end as u64);
+
+        if duration != Some(self.duration()) {
+            mismatches.push(Mismatch::Duration {
+                recorded: self.duration(),
+                simulated: duration,
+            });
+        }
+
+        Verification {
+            score,
+            grade: game.grade(),
+            duration,
+            mismatches,
+        }
+    }
+
+    fn simulate(&self, rules: &Ruleset) -> (Game, Option<u128>) {
+        let mut simulator = Simulator::new(self.clone(), rules);
+        let limit = simulator.last_input() + IDLE_LIMIT;
+
+        while simulator.step().is_some() {
+            if simulator.tick() > limit {
+                return (simulator.game().clone(), None);
+            }
+        }
+
+        (simulator.game().clone(), Some(simulator.tick()))
+    }
+
+    fn simulate_versus(&self, rules: &Ruleset) -> (Match, Option<u128>) {
+        let mut replay = self.clone();
+        let mut versus = Match::from_replay(&replay, rules);
+        let mut frames = [replay.next(), replay.next_opponent()];
+        let (mut tick, mut last_input) = (0, 0);
+
+        loop {
+            tick += 1;
+
+            let mut inputs = [Input::default(); 2];
+
+            for player in 0..2 {
+                // The first frame is recorded at 0, before the first tick
+                while let Some(frame) = frames[player].filter(|frame| frame.time <= tick) {
+                    inputs[player] = inputs[player].merge(frame.input);
+                    last_input = tick;
+                    frames[player] = match player {
+                        0 => replay.next(),
+                        _ => replay.next_opponent(),
+                    };
+                }
+            }
+
+            versus.tick(inputs, tick);
+
+            if versus.over() {
+                return (versus, Some(tick));
+            }
+
+            if frames.iter().all(Option::is_none) && tick - last_input > IDLE_LIMIT {
+                return (versus, None);
+            }
+        }
+    }
+}
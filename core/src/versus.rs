@@ -0,0 +1,136 @@
+use crate::{
+    attack::{AttackTable, GarbageQueue},
+    Bag, Board, Game, GameMode, GameType, Input, Replay, Ruleset, TickResult, TickType,
+};
+
+// Two games on the same seed, rows sent by one player are queued up for the other
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    players: [Game; 2],
+    queues: [GarbageQueue; 2],
+    over: bool,
+    winner: Option<usize>,
+}
+
+impl Match {
+    // Milliseconds before sent garbage may enter the opponent's board
+    pub const GARBAGE_DELAY: u128 = 500;
+
+    pub fn new(seed: u64) -> Self {
+        Self::from_boards([Board::new(Bag::new(seed)), Board::new(Bag::new(seed))])
+    }
+
+    pub fn from_boards(boards: [Board; 2]) -> Self {
+        Self::with_rules(
+            boards,
+            AttackTable::default(),
+            Self::GARBAGE_DELAY,
+            &Ruleset::guideline(),
+        )
+    }
+
+    // The starting position of a recorded versus match, played by the rules it was recorded with
+    pub fn from_replay(replay: &Replay, rules: &Ruleset) -> Self {
+        let seed = replay.seed();
+        let boards = [Board::new(Bag::new(seed)), Board::new(Bag::new(seed))];
+
+        Self::with_rules(
+            boards,
+            AttackTable::default(),
+            replay.kind().garbage_delay() as u128,
+            rules,
+        )
+    }
+
+    // Both players always play by the same rules
+    pub fn with_rules(
+        boards: [Board; 2],
+        table: AttackTable,
+        delay: u128,
+        rules: &Ruleset,
+    ) -> Self {
+        let [one, two] = boards.map(|board| {
+            let kind = GameType::new(GameMode::Versus, delay as u64);
+            let mut game = Game::from_board_with_rules(board, kind, rules);
+            game.set_attack_table(table.clone());
+
+            game
+        });
+
+        Self {
+            players: [one, two],
+            queues: [GarbageQueue::new(delay), GarbageQueue::new(delay)],
+            over: false,
+            winner: None,
+        }
+    }
+
+    pub fn tick(&mut self, inputs: [Input; 2], tick: u128) -> [TickResult; 2] {
+        let results = [
+            self.players[0].step(inputs[0], tick),
+            self.players[1].step(inputs[1], tick),
+        ];
+
+        if self.over {
+            return results;
+        }
+
+        // Quitting ends the match without a winner
+        if inputs.iter().any(|input| input.quit) {
+            self.over = true;
+            return results;
+        }
+
+        let mut lost = [false; 2];
+
+        for (player, res) in results.iter().enumerate() {
+            lost[player] |= res.kind() == TickType::GameOver;
+
+            if !res.placed() || lost[player] {
+                continue;
+            }
+
+            // Sent rows first cancel what is queued up, garbage only rises after a piece that did not clear
+            let attack = self.players[player].attack().last();
+            let attack = self.queues[player].cancel(attack);
+
+            self.queues[1 - player].push(attack, tick);
+
+            if res.lines() == 0 {
+                let rows = self.queues[player].take(tick);
+                lost[player] |= !self.players[player].receive_garbage(rows);
+            }
+        }
+
+        self.over = lost[0] || lost[1];
+        self.winner = match lost {
+            [true, false] => Some(1),
+            [false, true] => Some(0),
+            _ => None,
+        };
+
+        results
+    }
+
+    pub fn player(&self, player: usize) -> &Game {
+        &self.players[player]
+    }
+
+    // Rows of garbage a player has sent, including those spent on cancelling
+    pub fn sent(&self, player: usize) -> u64 {
+        self.players[player].attack().sent()
+    }
+
+    pub fn queue(&self, player: usize) -> &GarbageQueue {
+        &self.queues[player]
+    }
+
+    pub fn over(&self) -> bool {
+        self.over
+    }
+
+    // None while playing, and for a draw or when the match was quit
+    pub fn winner(&self) -> Option<usize> {
+        self.winner
+    }
+}